@@ -0,0 +1,91 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use geode_cli_installer::utils::vdf::Vdf;
+use std::fmt::Write;
+use std::hint::black_box;
+
+/// A `localconfig.vdf` as Steam writes it for an account owning `apps` games
+fn local_config(apps: usize) -> String {
+    let mut out = String::from("\"UserLocalConfigStore\"\n{\n\t\"Software\"\n\t{\n\t\t\"Valve\"\n\t\t{\n\t\t\t\"Steam\"\n\t\t\t{\n\t\t\t\t\"apps\"\n\t\t\t\t{\n");
+    for i in 0..apps {
+        let app_id = 10 + i * 10;
+        writeln!(out, "\t\t\t\t\t\"{}\"\n\t\t\t\t\t{{", app_id).unwrap();
+        writeln!(out, "\t\t\t\t\t\t\"LastPlayed\"\t\t\"{}\"", 1_700_000_000 + i).unwrap();
+        writeln!(out, "\t\t\t\t\t\t\"Playtime\"\t\t\"{}\"", i * 37 % 5000).unwrap();
+        out.push_str("\t\t\t\t\t\t\"cloud\"\n\t\t\t\t\t\t{\n\t\t\t\t\t\t\t\"last_sync_state\"\t\t\"synchronized\"\n\t\t\t\t\t\t}\n");
+        out.push_str("\t\t\t\t\t\t\"autocloud\"\n\t\t\t\t\t\t{\n\t\t\t\t\t\t\t\"lastlaunch\"\t\t\"1700000000\"\n\t\t\t\t\t\t\t\"lastexit\"\t\t\"1700003600\"\n\t\t\t\t\t\t}\n");
+        if i % 7 == 0 {
+            out.push_str("\t\t\t\t\t\t\"LaunchOptions\"\t\t\"PROTON_LOG=1 WINEDLLOVERRIDES=\\\"dxgi=n\\\" %command% -novid\"\n");
+        }
+        out.push_str("\t\t\t\t\t}\n");
+    }
+    out.push_str("\t\t\t\t}\n");
+    // Steam keeps lots of unrelated state next to the app settings
+    out.push_str("\t\t\t\t\"FriendStoreLocalPrefs_76561197960287930\"\t\t\"{\\\"ePerFriendDisplayMode\\\":1}\"\n");
+    out.push_str("\t\t\t}\n\t\t}\n\t}\n\t\"WebStorage\"\n\t{\n");
+    for i in 0..apps / 4 {
+        writeln!(out, "\t\t\"CloudStorage_{}\"\t\t\"{{\\\"key\\\":\\\"value {}\\\",\\\"path\\\":\\\"C:\\\\\\\\Users\\\\\\\\steam\\\"}}\"", i, i).unwrap();
+    }
+    out.push_str("\t}\n}\n");
+    out
+}
+
+/// A `libraryfolders.vdf` with `libraries` libraries sharing `apps` games
+fn library_folders(libraries: usize, apps: usize) -> String {
+    let mut out = String::from("\"libraryfolders\"\n{\n");
+    for library in 0..libraries {
+        writeln!(out, "\t\"{}\"\n\t{{\n\t\t\"path\"\t\t\"/mnt/games{}/SteamLibrary\"", library, library).unwrap();
+        out.push_str("\t\t\"label\"\t\t\"\"\n\t\t\"contentid\"\t\t\"4132884245467836163\"\n\t\t\"totalsize\"\t\t\"2000381014016\"\n");
+        out.push_str("\t\t\"update_clean_bytes_tally\"\t\t\"0\"\n\t\t\"time_last_update_verified\"\t\t\"1700000000\"\n\t\t\"apps\"\n\t\t{\n");
+        for app in (library..apps).step_by(libraries) {
+            writeln!(out, "\t\t\t\"{}\"\t\t\"{}\"", 10 + app * 10, 1_000_000 + app * 4099).unwrap();
+        }
+        out.push_str("\t\t}\n\t}\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    let inputs = [
+        ("libraryfolders", library_folders(4, 2000)),
+        ("localconfig_small", local_config(50)),
+        ("localconfig_large", local_config(5000)),
+    ];
+    for (name, text) in &inputs {
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), text, |b, text| {
+            b.iter(|| Vdf::parse(black_box(text)).unwrap());
+        });
+    }
+    group.finish();
+}
+
+fn write(c: &mut Criterion) {
+    let mut group = c.benchmark_group("write");
+    let text = local_config(5000);
+    let vdf = Vdf::parse(&text).unwrap();
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("localconfig_large", |b| b.iter(|| black_box(&vdf).to_string()));
+    group.finish();
+}
+
+/// What the launch options install does: parse, change one value, write back
+fn edit_launch_options(c: &mut Criterion) {
+    let mut group = c.benchmark_group("edit");
+    let text = local_config(5000);
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("localconfig_large", |b| {
+        b.iter(|| {
+            let mut vdf = Vdf::parse(black_box(&text)).unwrap();
+            vdf.local_config_apps_mut()
+                .block_or_insert("322170")
+                .set_string("LaunchOptions", "WINEDLLOVERRIDES=\"xinput1_4=n,b\" %command%");
+            vdf.to_string()
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse, write, edit_launch_options);
+criterion_main!(benches);
@@ -0,0 +1,686 @@
+use clap::{Args, Parser, Subcommand};
+use colored::*;
+use std::path::{Path, PathBuf};
+
+use geode_cli_installer::errors::InstallerError;
+use geode_cli_installer::utils::download_cache::DownloadCache;
+use geode_cli_installer::utils::registry_backup::RegistryBackups;
+use geode_cli_installer::utils::geode_installer::{DllOverride, GeodeInstaller, InstallOptions, InstallationPaths, OverrideScope};
+use geode_cli_installer::utils::registry_backend::{FileBackend, RegistryBackend, RegistryBackendKind, WineBackend};
+use geode_cli_installer::utils::steam_game_finder::{SteamGameFinder, SteamInstallation};
+
+use crate::UserInterface;
+
+/// Install Geode into Geometry Dash on Linux.
+///
+/// Runs the interactive menu when no command is given.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Answer "yes" to every confirmation prompt
+    #[arg(short, long, global = true)]
+    pub yes: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Install Geode
+    Install(InstallArgs),
+    /// Remove Geode and revert the Wine registry changes
+    Uninstall(UninstallArgs),
+    /// Upgrade an existing Geode installation, keeping mods and settings
+    Update(InstallArgs),
+    /// Show where Geometry Dash is and whether Geode is installed
+    Status(TargetArgs),
+    /// Check installed Geode files against the installation manifest
+    Verify(TargetArgs),
+    /// List Geode loader releases available for installation
+    Versions {
+        /// Include prereleases
+        #[arg(long)]
+        prerelease: bool,
+    },
+    /// Manage downloaded Geode archives
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Inspect and restore the Wine registry backups taken before each change
+    Registry {
+        #[command(subcommand)]
+        action: RegistryAction,
+    },
+    /// Inspect Steam installations and the games in them
+    Steam {
+        #[command(subcommand)]
+        action: SteamAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SteamAction {
+    /// List every game installed in Steam's libraries
+    List {
+        /// Steam installation to use (default: ask when there are several, or the first with --yes)
+        #[arg(long, value_name = "PATH")]
+        steam_root: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RegistryAction {
+    /// List registry backups, newest first
+    Backups(TargetArgs),
+    /// Put a registry backup back in place
+    Restore {
+        #[command(flatten)]
+        target: TargetArgs,
+
+        /// Backup to restore, as shown by `registry backups` (default: ask, or the newest with --yes)
+        #[arg(long, value_name = "NAME")]
+        backup: Option<String>,
+
+        /// If Geometry Dash or another Wine program is running in the prefix, wait for it to exit
+        #[arg(long)]
+        wait: bool,
+    },
+    /// Compare the xinput1_4 override in user.reg with what `wine reg query` reports
+    Check {
+        #[command(flatten)]
+        target: TargetArgs,
+
+        /// wine binary to ask, e.g. Proton's files/bin/wine (default: wine from PATH)
+        #[arg(long, value_name = "PATH")]
+        wine_binary: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheAction {
+    /// List cached archives
+    List,
+    /// Delete cached archives
+    Clean {
+        /// Only evict least recently used archives until the cache fits in this many megabytes
+        #[arg(long, value_name = "MB")]
+        max_size: Option<u64>,
+    },
+}
+
+impl Command {
+    fn install_options(&self) -> InstallOptions {
+        match self {
+            Command::Install(args) | Command::Update(args) => InstallOptions {
+                version: args.version.clone(),
+                prerelease: args.prerelease,
+                require_checksum: args.require_checksum,
+                archive: args.from_zip.clone(),
+                no_cache: args.no_cache,
+                cache_max_size: args.cache_max_size.map(megabytes),
+                retries: args.retries,
+                override_scope: args.override_scope,
+                registry_backups: args.keep_registry_backups,
+                wait: args.wait,
+                registry_backend: args.registry.registry_backend,
+                wine_binary: args.registry.wine_binary.clone(),
+                launch_options: args.launch_options,
+                ..Default::default()
+            },
+            Command::Uninstall(args) => InstallOptions {
+                wait: args.wait,
+                registry_backend: args.registry.registry_backend,
+                wine_binary: args.registry.wine_binary.clone(),
+                ..Default::default()
+            },
+            Command::Registry { action: RegistryAction::Restore { wait, .. } } => InstallOptions {
+                wait: *wait,
+                ..Default::default()
+            },
+            _ => InstallOptions::default(),
+        }
+    }
+
+    fn target(&self) -> Option<&TargetArgs> {
+        match self {
+            Command::Install(args) | Command::Update(args) => Some(&args.target),
+            Command::Uninstall(args) => Some(&args.target),
+            Command::Status(target) | Command::Verify(target) => Some(target),
+            Command::Registry { action } => match action {
+                RegistryAction::Backups(target) => Some(target),
+                RegistryAction::Restore { target, .. } | RegistryAction::Check { target, .. } => Some(target),
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct TargetArgs {
+    /// Use Steam's Geometry Dash and its Proton prefix (default)
+    #[arg(long, conflicts_with = "wine")]
+    steam: bool,
+
+    /// Use a custom Wine prefix and game directory
+    #[arg(long, requires_all = ["prefix", "game_dir"])]
+    wine: bool,
+
+    /// Wine prefix path
+    #[arg(long, value_name = "PATH", requires = "wine")]
+    prefix: Option<PathBuf>,
+
+    /// Geometry Dash directory
+    #[arg(long, value_name = "PATH", requires = "wine")]
+    game_dir: Option<PathBuf>,
+
+    /// Steam installation to use, the directory containing steamapps
+    /// (default: ask when there are several, or the first with --yes)
+    #[arg(long, value_name = "PATH", conflicts_with = "wine")]
+    steam_root: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct InstallArgs {
+    #[command(flatten)]
+    target: TargetArgs,
+
+    /// Geode loader tag to install, e.g. v4.2.0 (default: latest)
+    #[arg(long, value_name = "TAG")]
+    version: Option<String>,
+
+    /// Allow a prerelease when installing the latest version
+    #[arg(long, conflicts_with = "version")]
+    prerelease: bool,
+
+    /// Abort if the archive's checksum cannot be verified
+    #[arg(long)]
+    require_checksum: bool,
+
+    /// Install from a local geode-<tag>-win.zip instead of downloading
+    #[arg(long, value_name = "ZIP", conflicts_with_all = ["prerelease", "require_checksum"])]
+    from_zip: Option<PathBuf>,
+
+    /// Always download, ignoring previously cached archives
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Keep the archive cache below this many megabytes
+    #[arg(long, value_name = "MB", conflicts_with = "no_cache")]
+    cache_max_size: Option<u64>,
+
+    /// Retry failed downloads this many times, with exponential backoff
+    #[arg(long, value_name = "N")]
+    retries: Option<u32>,
+
+    /// Apply the xinput1_4 override to the whole prefix or only Geometry Dash
+    /// (default: prefix for Proton, app for custom Wine prefixes)
+    #[arg(long, value_name = "prefix|app")]
+    override_scope: Option<OverrideScope>,
+
+    /// Keep this many user.reg backups in the prefix (default: 10)
+    #[arg(long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    keep_registry_backups: Option<usize>,
+
+    /// If Geometry Dash or another Wine program is running in the prefix, wait for it to exit;
+    /// if Steam is still downloading or updating Geometry Dash, wait for it to finish
+    #[arg(long)]
+    wait: bool,
+
+    /// Put WINEDLLOVERRIDES="xinput1_4=n,b" into Geometry Dash's Steam launch options
+    /// instead of editing the Proton prefix registry (Steam must be closed)
+    #[arg(long, conflicts_with_all = ["wine", "override_scope"])]
+    launch_options: bool,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+#[derive(Args)]
+pub struct RegistryArgs {
+    /// Edit user.reg directly (file) or go through `wine reg` (wine)
+    #[arg(long, value_name = "file|wine", default_value = "file")]
+    registry_backend: RegistryBackendKind,
+
+    /// wine binary for the wine backend, e.g. Proton's files/bin/wine (default: wine from PATH)
+    #[arg(long, value_name = "PATH")]
+    wine_binary: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct UninstallArgs {
+    #[command(flatten)]
+    target: TargetArgs,
+
+    /// Also delete the geode/ folder with your mods and settings
+    #[arg(long)]
+    purge: bool,
+
+    /// If Geometry Dash or another Wine program is running in the prefix, wait for it to exit
+    #[arg(long)]
+    wait: bool,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+impl TargetArgs {
+    fn wine_paths(&self) -> Option<InstallationPaths> {
+        if self.steam || !self.wine {
+            return None;
+        }
+
+        Some(InstallationPaths {
+            game_path: self.game_dir.clone()?,
+            proton_prefix: self.prefix.clone()?,
+        })
+    }
+
+    fn resolve(&self, installer: &GeodeInstaller) -> Result<InstallationPaths, InstallerError> {
+        match self.wine_paths() {
+            Some(paths) => Ok(paths),
+            None => installer.locate_geometry_dash(),
+        }
+    }
+}
+
+pub fn run(command: Command, yes: bool) -> Result<(), InstallerError> {
+    let mut options = command.install_options();
+    if let Some(target) = command.target()
+        && target.wine_paths().is_none()
+    {
+        options.steam_root = match &target.steam_root {
+            Some(steam_root) => Some(steam_root.clone()),
+            None => choose_steam_root(yes)?,
+        };
+    }
+
+    let installer = GeodeInstaller::with_options(options)
+        .map_err(|e| InstallerError::Init(e.to_string()))?;
+    let installer = &installer;
+
+    match command {
+        Command::Install(args) => install(installer, &args, yes),
+        Command::Uninstall(args) => uninstall(installer, &args, yes),
+        Command::Update(args) => update(installer, &args, yes),
+        Command::Status(target) => status(installer, &target),
+        Command::Verify(target) => verify(installer, &target),
+        Command::Versions { prerelease } => versions(installer, prerelease),
+        Command::Cache { action } => cache(action),
+        Command::Registry { action } => registry(installer, action, yes),
+        Command::Steam { action } => steam(action, yes),
+    }
+}
+
+fn install(installer: &GeodeInstaller, args: &InstallArgs, yes: bool) -> Result<(), InstallerError> {
+    match args.target.wine_paths() {
+        Some(paths) => {
+            confirm(&format!("Install Geode to {:?}?", paths.game_path), yes)?;
+            confirm_override_fix(installer, &paths.proton_prefix, yes)?;
+            installer.install_to_wine(&paths.proton_prefix, &paths.game_path)?;
+        }
+        None if args.launch_options => {
+            // WINEDLLOVERRIDES takes precedence over the registry, so a conflicting registry value doesn't matter
+            confirm("Install Geode to Steam's Geometry Dash and set its launch options?", yes)?;
+            installer.install_to_steam()?;
+        }
+        None => {
+            confirm("Install Geode to Steam's Geometry Dash?", yes)?;
+            confirm_override_fix(installer, &installer.locate_geometry_dash()?.proton_prefix, yes)?;
+            installer.install_to_steam()?;
+        }
+    }
+
+    println!("{}", "✅ Geode has been successfully installed!".green().bold());
+    Ok(())
+}
+
+fn update(installer: &GeodeInstaller, args: &InstallArgs, yes: bool) -> Result<(), InstallerError> {
+    if args.target.wine_paths().is_none() {
+        installer.ensure_game_ready()?;
+    }
+    let paths = args.target.resolve(installer)?;
+    let check = installer.check_update(&paths.game_path)?;
+
+    if check.is_up_to_date() {
+        println!("{}", format!("✅ Geode {} is already up to date.", check.available).green().bold());
+        return Ok(());
+    }
+
+    let installed = check.installed.as_deref().unwrap_or("unknown version");
+    println!("{} {} → {}", "Geode:".white().bold(), installed, check.available.green().bold());
+    if check.installed.is_none() {
+        println!("No install manifest was found, so Geode will be reinstalled to record which files it owns.");
+    }
+
+    let plan = installer.plan_update(&paths.game_path, &check)?;
+    if plan.summary.is_empty() {
+        println!("No loader files change.");
+    }
+    for path in &plan.summary.added {
+        println!("  {} {}", "+".green().bold(), path.display());
+    }
+    for path in &plan.summary.changed {
+        println!("  {} {}", "~".yellow().bold(), path.display());
+    }
+    for path in &plan.summary.removed {
+        println!("  {} {}", "-".red().bold(), path.display());
+    }
+
+    confirm(&format!("Update Geode in {:?}?", paths.game_path), yes)?;
+    if !args.launch_options {
+        confirm_override_fix(installer, &paths.proton_prefix, yes)?;
+    }
+    installer.apply_update(&paths.proton_prefix, &paths.game_path, &plan)?;
+
+    println!("{}", format!("✅ Geode has been updated to {}!", check.available).green().bold());
+    Ok(())
+}
+
+fn uninstall(installer: &GeodeInstaller, args: &UninstallArgs, yes: bool) -> Result<(), InstallerError> {
+    let keep_data = !args.purge;
+    let question = if keep_data {
+        "Remove Geode? Your mods and settings will be kept."
+    } else {
+        "Remove Geode together with all mods and settings?"
+    };
+
+    match args.target.wine_paths() {
+        Some(paths) => {
+            confirm(question, yes)?;
+            installer.uninstall_from_wine(&paths.proton_prefix, &paths.game_path, keep_data)?;
+        }
+        None => {
+            confirm(question, yes)?;
+            installer.uninstall_from_steam(keep_data)?;
+        }
+    }
+
+    println!("{}", "✅ Geode has been successfully removed!".green().bold());
+    Ok(())
+}
+
+fn status(installer: &GeodeInstaller, target: &TargetArgs) -> Result<(), InstallerError> {
+    let paths = target.resolve(installer)?;
+    let status = installer.status(&paths.proton_prefix, &paths.game_path)?;
+
+    println!("{} {:?}", "Game directory:".white().bold(), paths.game_path);
+    println!("{} {:?}", "Wine prefix:   ".white().bold(), paths.proton_prefix);
+
+    if let Some(manifest) = &status.manifest {
+        println!(
+            "{} {} ({} files)",
+            "Geode:         ".white().bold(),
+            format!("{} installed", manifest.tag).green(),
+            manifest.files.len()
+        );
+    } else if status.geode_installed {
+        println!("{} {}", "Geode:         ".white().bold(), "installed (no manifest)".green());
+    } else {
+        println!("{} {}", "Geode:         ".white().bold(), "not installed".red());
+    }
+
+    match &status.launch_option_override {
+        Some(mode) => println!("{} {} (Steam launch options)", "xinput1_4:     ".white().bold(), mode),
+        None => print_dll_override(status.dll_override),
+    }
+
+    if target.wine_paths().is_none()
+        && let Some(game) = installer.steam_game_info()
+    {
+        println!("{} {}", "Proton:        ".white().bold(), game.compat.summary("GD"));
+        for warning in game.compat.warnings() {
+            println!("{} {}", "⚠️".yellow(), warning.yellow());
+        }
+    }
+
+    Ok(())
+}
+
+fn print_dll_override(dll_override: Option<DllOverride>) {
+    match dll_override {
+        Some(dll_override) if !dll_override.loads_native() => println!(
+            "{} {} ({} scope) {}",
+            "xinput1_4:     ".white().bold(),
+            format!("\"{}\"", dll_override.value).red(),
+            dll_override.scope,
+            "— Wine won't load Geode; reinstall to fix".red()
+        ),
+        Some(dll_override) => println!(
+            "{} {} ({} scope)",
+            "xinput1_4:     ".white().bold(),
+            dll_override.value,
+            dll_override.scope
+        ),
+        None => println!("{} {}", "xinput1_4:     ".white().bold(), "no override".red()),
+    }
+}
+
+fn verify(installer: &GeodeInstaller, target: &TargetArgs) -> Result<(), InstallerError> {
+    let paths = target.resolve(installer)?;
+    let problems = installer.verify(&paths.game_path)?;
+
+    if problems.is_empty() {
+        println!("{}", "✅ All Geode files match the installation manifest.".green().bold());
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{} {}", "•".red().bold(), problem);
+    }
+    Err(InstallerError::Verification(format!("{} file(s) differ from the manifest", problems.len())))
+}
+
+fn versions(installer: &GeodeInstaller, prerelease: bool) -> Result<(), InstallerError> {
+    for release in installer.list_versions(prerelease)? {
+        let date = release.created_at.as_deref()
+            .and_then(|date| date.get(..10))
+            .unwrap_or("");
+        let gd = release.gd_version.map(|gd| format!("GD {}", gd)).unwrap_or_default();
+        let label = if release.prerelease { "prerelease".yellow().to_string() } else { String::new() };
+
+        println!("{:<16} {:<10} {:<12} {}", release.tag.white().bold(), date, gd, label);
+    }
+    Ok(())
+}
+
+fn cache(action: CacheAction) -> Result<(), InstallerError> {
+    let cache = DownloadCache::new()
+        .ok_or_else(|| InstallerError::Unknown("Can't determine the cache directory".into()))?;
+
+    match action {
+        CacheAction::List => {
+            let entries = cache.entries()?;
+            if entries.is_empty() {
+                println!("The cache at {:?} is empty.", cache.root());
+                return Ok(());
+            }
+
+            for entry in &entries {
+                println!("{:<16} {:>8.1} MB  sha256:{}", entry.tag.white().bold(), to_megabytes(entry.size), entry.sha256);
+            }
+            println!("{} {:.1} MB in {:?}", "Total:".white().bold(), to_megabytes(cache.total_size()?), cache.root());
+        }
+        CacheAction::Clean { max_size: Some(max_size) } => {
+            for entry in cache.prune(megabytes(max_size))? {
+                println!("Removed {} ({:.1} MB)", entry.tag, to_megabytes(entry.size));
+            }
+        }
+        CacheAction::Clean { max_size: None } => {
+            let freed = cache.clean()?;
+            println!("{}", format!("✅ Freed {:.1} MB.", to_megabytes(freed)).green().bold());
+        }
+    }
+    Ok(())
+}
+
+fn registry(installer: &GeodeInstaller, action: RegistryAction, yes: bool) -> Result<(), InstallerError> {
+    let (target, name) = match &action {
+        RegistryAction::Backups(target) => (target, None),
+        RegistryAction::Restore { target, backup, .. } => (target, backup.as_deref()),
+        RegistryAction::Check { target, wine_binary } => return registry_check(installer, target, wine_binary.clone()),
+    };
+    let paths = target.resolve(installer)?;
+    let backups = RegistryBackups::in_prefix(&paths.proton_prefix);
+    let list = backups.list()?;
+
+    if list.is_empty() {
+        println!("No registry backups in {:?}", backups.dir());
+        return match action {
+            RegistryAction::Restore { .. } => Err(InstallerError::Installation("Nothing to restore".into())),
+            _ => Ok(()),
+        };
+    }
+
+    if name.is_none() {
+        for (i, backup) in list.iter().enumerate() {
+            println!("{:>3}. {}  ({})", i + 1, backup.name().bold(), backup.file);
+        }
+    }
+    if let RegistryAction::Backups(_) = action {
+        return Ok(());
+    }
+
+    let backup = match name {
+        Some(name) => list.iter()
+            .find(|backup| backup.name() == name)
+            .ok_or_else(|| InstallerError::Installation(format!("No registry backup named {}", name)))?,
+        None if yes => &list[0],
+        None => &list[read_choice("Backup to restore [1]: ", list.len())?],
+    };
+
+    confirm(&format!("Restore {} from {}?", backup.file, backup.name()), yes)?;
+    installer.restore_registry_backup(&paths.proton_prefix, backup)?;
+
+    println!("{}", format!("✅ Restored {} from {}", backup.file, backup.name()).green().bold());
+    Ok(())
+}
+
+fn registry_check(installer: &GeodeInstaller, target: &TargetArgs, wine_binary: Option<PathBuf>) -> Result<(), InstallerError> {
+    let paths = target.resolve(installer)?;
+    let wine = WineBackend::new(wine_binary.unwrap_or_else(|| PathBuf::from("wine")));
+
+    let from_file = installer.dll_override_from(&FileBackend, &paths.proton_prefix)?;
+    let from_wine = installer.dll_override_from(&wine, &paths.proton_prefix)?;
+    // Reading started a wineserver; let it exit so later edits aren't refused
+    wine.flush(&paths.proton_prefix)?;
+
+    let describe = |dll_override: &Option<DllOverride>| match dll_override {
+        Some(dll_override) => format!("{} ({} scope)", dll_override.value, dll_override.scope),
+        None => "no override".to_string(),
+    };
+    println!("{} {}", "user.reg:      ".white().bold(), describe(&from_file));
+    println!("{} {}", "wine reg query:".white().bold(), describe(&from_wine));
+
+    if from_file != from_wine {
+        return Err(InstallerError::Verification("user.reg and Wine disagree about the xinput1_4 override".into()));
+    }
+    println!("{}", "✅ user.reg matches what Wine reports.".green().bold());
+    Ok(())
+}
+
+fn steam(action: SteamAction, yes: bool) -> Result<(), InstallerError> {
+    let SteamAction::List { steam_root } = action;
+    let finder = match steam_root.map_or_else(|| choose_steam_root(yes), |root| Ok(Some(root)))? {
+        Some(steam_root) => SteamGameFinder::at(steam_root),
+        None => SteamGameFinder::new(),
+    };
+    let steam = finder.installation()
+        .ok_or_else(|| InstallerError::Installation("Can't find Steam installation".into()))?;
+    println!("{} {} ({})", "Steam root:".white().bold(), steam.root.display(), steam.packaging);
+
+    let apps = finder.installed_apps();
+    for app in &apps {
+        let build = app.build_id.map(|build| format!("build {}", build)).unwrap_or_default();
+        let updated = app.last_updated.map(date).unwrap_or_default();
+        let note = match (app.state_flags.is_fully_installed(), &app.proton_prefix) {
+            (false, _) => app.state_flags.to_string().yellow().to_string(),
+            (true, Some(_)) => "Proton".to_string(),
+            (true, None) => String::new(),
+        };
+
+        println!(
+            "{:<10} {:<40} {:>9.1} MB  {:<16} {:<10} {}",
+            app.app_id.white().bold(),
+            app.name,
+            to_megabytes(app.size_on_disk),
+            build,
+            updated,
+            note
+        );
+    }
+    println!("{} {} apps", "Total:".white().bold(), apps.len());
+    Ok(())
+}
+
+/// `YYYY-MM-DD` of a Unix timestamp, in UTC
+fn date(timestamp: u64) -> String {
+    // Days since 1970-01-01 to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = timestamp / 86400 + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn megabytes(mb: u64) -> u64 {
+    mb * 1024 * 1024
+}
+
+fn to_megabytes(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+/// Explain an xinput1_4 override that keeps Geode from loading and ask before it is rewritten
+pub fn confirm_override_fix(installer: &GeodeInstaller, prefix: &Path, yes: bool) -> Result<(), InstallerError> {
+    if let Some(dll_override) = installer.conflicting_dll_override(prefix)? {
+        println!(
+            "{} xinput1_4 is overridden to \"{}\" ({} scope), so Wine uses its builtin DLL and Geode never loads.",
+            "⚠️".yellow(),
+            dll_override.value,
+            dll_override.scope
+        );
+        confirm("Change the override to native,builtin?", yes)?;
+    }
+    Ok(())
+}
+
+/// Ask which Steam installation to use when there are several; `None` leaves it to the finder
+pub fn choose_steam_root(yes: bool) -> Result<Option<PathBuf>, InstallerError> {
+    let installations = SteamInstallation::discover();
+    if installations.len() < 2 || yes {
+        return Ok(None);
+    }
+
+    println!("{}", "Several Steam installations were found:".white().bold());
+    for (i, installation) in installations.iter().enumerate() {
+        println!("{:>3}. {} ({})", i + 1, installation.root.display().to_string().bold(), installation.packaging);
+    }
+    let choice = read_choice("Steam installation to use [1]: ", installations.len())?;
+    Ok(Some(installations[choice].root.clone()))
+}
+
+/// Index of an item picked from a numbered list, the first one if nothing was entered
+fn read_choice(prompt: &str, count: usize) -> Result<usize, InstallerError> {
+    let input = UserInterface::read_input(prompt);
+    let choice: usize = if input.is_empty() {
+        1
+    } else {
+        input.parse().map_err(|_| InstallerError::NotANumber)?
+    };
+    (1..=count).contains(&choice).then(|| choice - 1).ok_or(InstallerError::InvalidNumber)
+}
+
+fn confirm(question: &str, yes: bool) -> Result<(), InstallerError> {
+    if yes || UserInterface::confirm(question) {
+        Ok(())
+    } else {
+        Err(InstallerError::Aborted)
+    }
+}
@@ -15,6 +15,36 @@ pub enum InstallerError {
     #[error("Installation failed: {0}")]
     Installation(String),
 
+    #[error("Download failed: {0}")]
+    Download(String),
+
+    #[error("Downloaded archive is corrupted: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+
+    #[error("Verification failed: {0}")]
+    Verification(String),
+
+    #[error("Invalid Wine registry file: {0}")]
+    Registry(String),
+
+    #[error("Wine prefix is in use: {0}")]
+    PrefixInUse(String),
+
+    #[error("Steam is running: {0}")]
+    SteamRunning(String),
+
+    #[error("Geometry Dash isn't ready: {0}")]
+    GameNotReady(String),
+
+    #[error("Invalid VDF file: {0}")]
+    Vdf(String),
+
+    #[error("File error: {0}")]
+    Io(io::Error),
+
+    #[error("Operation cancelled.")]
+    Aborted,
+
     #[error("An error occurred: {0}")]
     Unknown(String),
 }
@@ -23,11 +53,20 @@ impl InstallerError {
     pub fn format(&self) -> String {
         format!("❌ {}", self).red().bold().to_string()
     }
+
+    /// Process exit code used by the command-line interface
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            InstallerError::NotANumber | InstallerError::InvalidNumber => 2,
+            InstallerError::Aborted => 3,
+            _ => 1,
+        }
+    }
 }
 
 impl From<io::Error> for InstallerError {
     fn from(e: io::Error) -> Self {
-        InstallerError::Unknown(e.to_string())
+        InstallerError::Io(e)
     }
 }
 
@@ -1,17 +1,21 @@
+use clap::Parser;
 use colored::*;
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process;
 
-mod utils;
-mod errors;
+mod cli;
 
-use errors::InstallerError;
-use utils::geode_installer::GeodeInstaller;
+use cli::Cli;
+use geode_cli_installer::errors::InstallerError;
+use geode_cli_installer::utils::geode_installer::{GeodeInstaller, InstallOptions};
 
 enum MenuChoice {
     InstallToSteam,
     InstallToWine,
+    InstallFromArchive,
+    UninstallFromSteam,
+    UninstallFromWine,
     Quit,
 }
 
@@ -34,6 +38,9 @@ impl UserInterface {
         println!();
         println!("{} Install to {}", "1.".blue().bold(), "Steam".blue());
         println!("{} Install to {} prefix", "2.".magenta().bold(), "Wine".magenta());
+        println!("{} Install from a local {}", "3.".cyan().bold(), "zip archive".cyan());
+        println!("{} Uninstall from {}", "4.".blue().bold(), "Steam".blue());
+        println!("{} Uninstall from {} prefix", "5.".magenta().bold(), "Wine".magenta());
         println!("{} Quit", "0.".red().bold());
         println!();
     }
@@ -57,14 +64,22 @@ impl UserInterface {
         match n {
             1 => Ok(MenuChoice::InstallToSteam),
             2 => Ok(MenuChoice::InstallToWine),
+            3 => Ok(MenuChoice::InstallFromArchive),
+            4 => Ok(MenuChoice::UninstallFromSteam),
+            5 => Ok(MenuChoice::UninstallFromWine),
             0 => Ok(MenuChoice::Quit),
             _ => Err(InstallerError::InvalidNumber),
         }
     }
 
-    fn print_success() {
+    fn confirm(question: &str) -> bool {
+        let answer = Self::read_input(&format!("{} [y/N]: ", question));
+        matches!(answer.to_lowercase().as_str(), "y" | "yes")
+    }
+
+    fn print_success(message: &str) {
         println!();
-        println!("{}", "✅ Geode has been successfully installed!".green().bold());
+        println!("{}", format!("✅ {}", message).green().bold());
     }
 
     fn print_error(message: &InstallerError) {
@@ -77,17 +92,24 @@ impl UserInterface {
 
 struct InstallationHandler {
     installer: GeodeInstaller,
+    steam_root: Option<PathBuf>,
 }
 
 impl InstallationHandler {
     fn new() -> Result<Self, InstallerError> {
+        let steam_root = cli::choose_steam_root(false)?;
         Ok(Self {
-            installer: GeodeInstaller::new()?,
+            installer: GeodeInstaller::with_options(InstallOptions {
+                steam_root: steam_root.clone(),
+                ..Default::default()
+            })?,
+            steam_root,
         })
     }
 
     fn handle_steam_installation(&self) -> Result<(), InstallerError> {
         println!("{}", "🎮 Installing to Steam...".blue().bold());
+        cli::confirm_override_fix(&self.installer, &self.installer.locate_geometry_dash()?.proton_prefix, false)?;
         self.installer.install_to_steam()
     }
 
@@ -96,6 +118,7 @@ impl InstallationHandler {
 
         let game_path = UserInterface::read_input("Enter your Geometry Dash path: ");
         let wine_prefix = UserInterface::read_input("Enter your Wine prefix path: ");
+        cli::confirm_override_fix(&self.installer, Path::new(&wine_prefix), false)?;
 
         self.installer.install_to_wine(
             Path::new(&wine_prefix),
@@ -103,11 +126,62 @@ impl InstallationHandler {
         )
     }
 
-    fn execute(&self, choice: MenuChoice) -> Result<(), InstallerError> {
+    fn handle_archive_installation(&self) -> Result<(), InstallerError> {
+        println!("{}", "📦 Offline Installation".cyan().bold());
+
+        let archive = UserInterface::read_input("Enter the path to geode-<tag>-win.zip: ");
+        let installer = GeodeInstaller::with_options(InstallOptions {
+            archive: Some(PathBuf::from(archive)),
+            steam_root: self.steam_root.clone(),
+            ..Default::default()
+        })?;
+
+        if UserInterface::confirm("Install to Steam's Geometry Dash?") {
+            cli::confirm_override_fix(&installer, &installer.locate_geometry_dash()?.proton_prefix, false)?;
+            return installer.install_to_steam();
+        }
+
+        let game_path = UserInterface::read_input("Enter your Geometry Dash path: ");
+        let wine_prefix = UserInterface::read_input("Enter your Wine prefix path: ");
+        cli::confirm_override_fix(&installer, Path::new(&wine_prefix), false)?;
+
+        installer.install_to_wine(
+            Path::new(&wine_prefix),
+            Path::new(&game_path),
+        )
+    }
+
+    fn handle_steam_uninstallation(&self) -> Result<(), InstallerError> {
+        println!("{}", "🗑️ Uninstalling from Steam...".blue().bold());
+        let keep_data = !UserInterface::confirm("Also delete your mods and settings (geode/ folder)?");
+        self.installer.uninstall_from_steam(keep_data)
+    }
+
+    fn handle_wine_uninstallation(&self) -> Result<(), InstallerError> {
+        println!("{}", "🍷 Wine Uninstallation".magenta().bold());
+
+        let game_path = UserInterface::read_input("Enter your Geometry Dash path: ");
+        let wine_prefix = UserInterface::read_input("Enter your Wine prefix path: ");
+        let keep_data = !UserInterface::confirm("Also delete your mods and settings (geode/ folder)?");
+
+        self.installer.uninstall_from_wine(
+            Path::new(&wine_prefix),
+            Path::new(&game_path),
+            keep_data,
+        )
+    }
+
+    fn execute(&self, choice: MenuChoice) -> Result<&'static str, InstallerError> {
+        const INSTALLED: &str = "Geode has been successfully installed!";
+        const REMOVED: &str = "Geode has been successfully removed!";
+
         match choice {
-            MenuChoice::InstallToSteam => Ok(self.handle_steam_installation()?),
-            MenuChoice::InstallToWine => Ok(self.handle_wine_installation()?),
-            MenuChoice::Quit => Ok(()),
+            MenuChoice::InstallToSteam => self.handle_steam_installation().map(|_| INSTALLED),
+            MenuChoice::InstallToWine => self.handle_wine_installation().map(|_| INSTALLED),
+            MenuChoice::InstallFromArchive => self.handle_archive_installation().map(|_| INSTALLED),
+            MenuChoice::UninstallFromSteam => self.handle_steam_uninstallation().map(|_| REMOVED),
+            MenuChoice::UninstallFromWine => self.handle_wine_uninstallation().map(|_| REMOVED),
+            MenuChoice::Quit => Ok(""),
         }
     }
 }
@@ -124,7 +198,7 @@ fn run_interactive_loop(handler: &InstallationHandler) {
                 break;
             }
             Ok(choice) => match handler.execute(choice) {
-                Ok(_) => UserInterface::print_success(),
+                Ok(message) => UserInterface::print_success(message),
                 Err(e) => UserInterface::print_error(&e),
             },
             Err(e) => UserInterface::print_error(&e),
@@ -133,6 +207,16 @@ fn run_interactive_loop(handler: &InstallationHandler) {
 }
 
 fn main() {
+    let cli = Cli::parse();
+
+    if let Some(command) = cli.command {
+        if let Err(e) = cli::run(command, cli.yes) {
+            eprintln!("{}", e.format());
+            process::exit(e.exit_code());
+        }
+        return;
+    }
+
     let handler = InstallationHandler::new().map_err(|e| InstallerError::Init(e.to_string()))
         .unwrap_or_else(|err| {
             eprintln!("{}", err.format());
@@ -0,0 +1,210 @@
+use crate::utils::vdf::Vdf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where `config/config.vdf` keeps the compatibility tool chosen for each app
+const COMPAT_TOOL_MAPPING: &[&str] = &["InstallConfigStore", "Software", "Valve", "Steam", "CompatToolMapping"];
+/// `CompatToolMapping` entry holding the tool chosen for all games in Steam's settings
+const DEFAULT_MAPPING: &str = "0";
+
+/// A compatibility tool such as Proton, as installed in a library or `compatibilitytools.d`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompatTool {
+    /// The name Steam refers to it by in `CompatToolMapping`, e.g. `proton_9` or `GE-Proton9-20`
+    pub name: String,
+    /// e.g. `Proton 9.0 (Beta)`
+    pub display_name: String,
+    pub path: PathBuf,
+    /// From the tool's `version` file, e.g. `9.0-2`
+    pub version: Option<String>,
+    /// Installed by the user into `compatibilitytools.d` rather than by Steam
+    pub custom: bool,
+}
+
+impl CompatTool {
+    /// Proton from Steam, e.g. `steamapps/common/Proton 9.0 (Beta)`
+    fn official(path: PathBuf) -> Option<Self> {
+        let display_name = path.file_name()?.to_str()?.to_string();
+        Some(Self {
+            name: official_name(&display_name)?,
+            version: read_tool_version(&path),
+            display_name,
+            path,
+            custom: false,
+        })
+    }
+
+    /// Tools in `compatibilitytools.d/<dir>`, as declared by its `compatibilitytool.vdf`
+    fn custom(path: &Path) -> Vec<Self> {
+        let Ok(manifest) = Vdf::load(&path.join("compatibilitytool.vdf")) else {
+            return Vec::new();
+        };
+        let Some(tools) = manifest.path(&["compatibilitytools", "compat_tools"]) else {
+            return Vec::new();
+        };
+
+        tools.iter()
+            .filter_map(|(name, value)| {
+                let tool = value.as_block()?;
+                Some(Self {
+                    name: name.to_string(),
+                    display_name: tool.string("display_name").unwrap_or(name).to_string(),
+                    path: path.join(tool.string("install_path").unwrap_or(".")),
+                    version: read_tool_version(path),
+                    custom: true,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Which compatibility tool Steam runs an app with, and what its prefix was made by
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompatToolInfo {
+    /// The tool chosen in `CompatToolMapping`, `None` when Steam picks its default
+    pub tool_name: Option<String>,
+    /// The installed tool `tool_name` refers to
+    pub tool: Option<CompatTool>,
+    /// Proton version recorded in the app's `compatdata`, e.g. `8.0-104`
+    pub prefix_version: Option<String>,
+}
+
+impl CompatToolInfo {
+    /// The configuration of `app_id` in the Steam installation at `steam_root`
+    pub fn read(steam_root: &Path, app_id: &str, tools: &[CompatTool], compatdata: Option<&Path>) -> Self {
+        let tool_name = configured_tool(steam_root, app_id);
+        let tool = tool_name.as_ref().and_then(|name| tools.iter().find(|tool| tool.name == *name).cloned());
+
+        Self {
+            tool_name,
+            tool,
+            prefix_version: compatdata.and_then(prefix_version),
+        }
+    }
+
+    /// e.g. `GD uses Proton 9.0 (prefix created with 8.0)`
+    pub fn summary(&self, game: &str) -> String {
+        let tool = match (&self.tool, &self.tool_name) {
+            (Some(tool), _) => tool.display_name.clone(),
+            (None, Some(name)) => format!("{} (not installed)", name),
+            (None, None) => "Steam's default Proton".to_string(),
+        };
+
+        match &self.prefix_version {
+            Some(version) => format!("{} uses {} (prefix created with {})", game, tool, short_version(version)),
+            None => format!("{} uses {}", game, tool),
+        }
+    }
+
+    /// Combinations known to keep the game from starting
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if let (Some(name), None) = (&self.tool_name, &self.tool) {
+            warnings.push(format!("Steam is set to use {}, which isn't installed", name));
+        }
+
+        let tool_version = self.tool.as_ref().and_then(|tool| tool.version.as_deref()).and_then(proton_version);
+        let prefix_version = self.prefix_version.as_deref().and_then(proton_version);
+        if let (Some(tool_version), Some(prefix_version)) = (tool_version, prefix_version)
+            && tool_version < prefix_version
+        {
+            warnings.push(format!(
+                "The prefix was created by Proton {}.{}, which is newer than {}; Proton can't downgrade \
+                 a prefix, so switch back to a newer Proton or delete the prefix",
+                prefix_version.0,
+                prefix_version.1,
+                self.tool.as_ref().map_or("the selected tool", |tool| tool.display_name.as_str()),
+            ));
+        }
+
+        warnings
+    }
+}
+
+/// Proton versions in `steamapps/common` of every library and custom tools in `compatibilitytools.d`
+pub fn installed_tools(steam_root: &Path, library_folders: &[PathBuf]) -> Vec<CompatTool> {
+    let mut tools: Vec<CompatTool> = library_folders.iter()
+        .flat_map(|library| subdirectories(&library.join("common")))
+        .filter(|path| path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with("Proton")))
+        .filter_map(CompatTool::official)
+        .collect();
+
+    tools.extend(subdirectories(&steam_root.join("compatibilitytools.d")).iter().flat_map(|path| CompatTool::custom(path)));
+    tools
+}
+
+/// The tool chosen for `app_id` in Steam's settings, or for all games if it has none of its own
+pub fn configured_tool(steam_root: &Path, app_id: &str) -> Option<String> {
+    let config = Vdf::load(&steam_root.join("config/config.vdf")).ok()?;
+    let mapping = config.path(COMPAT_TOOL_MAPPING)?;
+
+    [app_id, DEFAULT_MAPPING].into_iter()
+        .filter_map(|key| mapping.block(key)?.string("name"))
+        .find(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+/// The Proton version that last set up a prefix, from `compatdata/<app id>/version`
+/// or else the first line of `config_info`
+pub fn prefix_version(compatdata: &Path) -> Option<String> {
+    let read_first_line = |file: &str| {
+        let text = fs::read_to_string(compatdata.join(file)).ok()?;
+        let line = text.lines().next()?.trim();
+        (!line.is_empty()).then(|| line.to_string())
+    };
+    read_first_line("version").or_else(|| read_first_line("config_info"))
+}
+
+/// Steam's name for its own Proton builds, e.g. `Proton 9.0 (Beta)` → `proton_9`,
+/// `Proton 5.13` → `proton_513` and `Proton - Experimental` → `proton_experimental`
+fn official_name(display_name: &str) -> Option<String> {
+    let rest = display_name.strip_prefix("Proton")?.trim_start_matches([' ', '-']);
+    let version = rest.split_whitespace().next()?;
+
+    if version.starts_with(|c: char| c.is_ascii_digit()) {
+        let version = version.strip_suffix(".0").unwrap_or(version);
+        Some(format!("proton_{}", version.replace('.', "")))
+    } else {
+        Some(format!("proton_{}", version.to_lowercase()))
+    }
+}
+
+/// A tool's `version` file holds a build timestamp and a name, e.g. `1716829420 proton-9.0-2`
+fn read_tool_version(path: &Path) -> Option<String> {
+    let text = fs::read_to_string(path.join("version")).ok()?;
+    let version = text.split_whitespace().last()?;
+    Some(version.strip_prefix("proton-").unwrap_or(version).to_string())
+}
+
+/// The first `major.minor` in a version string such as `8.0-104` or `experimental-9.0-20240520`
+fn proton_version(version: &str) -> Option<(u32, u32)> {
+    version.split(|c: char| !c.is_ascii_digit() && c != '.')
+        .filter_map(|part| part.split_once('.'))
+        .find_map(|(major, minor)| {
+            let minor = minor.split('.').next()?;
+            Some((major.parse().ok()?, minor.parse().ok()?))
+        })
+}
+
+/// `8.0` for `8.0-104`, anything else unchanged
+fn short_version(version: &str) -> String {
+    match proton_version(version) {
+        Some((major, minor)) if version.starts_with(|c: char| c.is_ascii_digit()) => format!("{}.{}", major, minor),
+        _ => version.to_string(),
+    }
+}
+
+fn subdirectories(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
@@ -0,0 +1,207 @@
+use crate::errors::InstallerError;
+use crate::utils::install_manifest::sha256_file;
+use homedir::my_home;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default upper bound for the cache size, in bytes
+pub const DEFAULT_MAX_SIZE: u64 = 512 * 1024 * 1024;
+
+const CACHE_DIR_NAME: &str = "geode-installer";
+const INDEX_FILE_NAME: &str = "index.json";
+const PARTIAL_EXTENSION: &str = "part";
+
+/// Release archives stored by content hash under `$XDG_CACHE_HOME/geode-installer/`,
+/// with an index mapping loader tags to hashes
+pub struct DownloadCache {
+    root: PathBuf,
+    max_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub tag: String,
+    pub sha256: String,
+    pub size: u64,
+    /// Unix timestamp of the last install that used this archive
+    pub last_used: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+    entries: Vec<CacheEntry>,
+}
+
+impl DownloadCache {
+    /// The per-user cache, or `None` if neither `$XDG_CACHE_HOME` nor a home directory is known
+    pub fn new() -> Option<Self> {
+        let base = env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| Some(my_home().ok()??.join(".cache")))?;
+
+        Some(Self::at(base.join(CACHE_DIR_NAME)))
+    }
+
+    pub fn at(root: PathBuf) -> Self {
+        Self {
+            root,
+            max_size: DEFAULT_MAX_SIZE,
+        }
+    }
+
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn entries(&self) -> Result<Vec<CacheEntry>, InstallerError> {
+        Ok(self.load_index()?.entries)
+    }
+
+    pub fn total_size(&self) -> Result<u64, InstallerError> {
+        Ok(self.entries()?.iter().map(|entry| entry.size).sum())
+    }
+
+    /// Path of the cached archive for `tag`, if present and intact
+    pub fn lookup(&self, tag: &str) -> Result<Option<PathBuf>, InstallerError> {
+        let mut index = self.load_index()?;
+        let Some(position) = index.entries.iter().position(|entry| entry.tag == tag) else {
+            return Ok(None);
+        };
+
+        let path = self.blob_path(&index.entries[position].sha256);
+        if !path.is_file() || sha256_file(&path)? != index.entries[position].sha256 {
+            // Damaged or removed behind our back: forget it and download again
+            let entry = index.entries.remove(position);
+            self.remove_blob_if_unused(&index, &entry.sha256)?;
+            self.save_index(&index)?;
+            return Ok(None);
+        }
+
+        // Keep the index ordered by recency so ties within a second still evict in LRU order
+        let mut entry = index.entries.remove(position);
+        entry.last_used = now();
+        index.entries.push(entry);
+        self.save_index(&index)?;
+        Ok(Some(path))
+    }
+
+    /// Move a verified archive into the cache, returning its new location
+    pub fn store(&self, tag: &str, archive: &Path) -> Result<PathBuf, InstallerError> {
+        fs::create_dir_all(&self.root)?;
+
+        let sha256 = sha256_file(archive)?;
+        let size = fs::metadata(archive)?.len();
+        let path = self.blob_path(&sha256);
+
+        if !path.exists() && fs::rename(archive, &path).is_err() {
+            // Different filesystem, e.g. a game library on another drive
+            fs::copy(archive, &path)?;
+        }
+
+        let mut index = self.load_index()?;
+        index.entries.retain(|entry| entry.tag != tag);
+        index.entries.push(CacheEntry {
+            tag: tag.to_string(),
+            sha256,
+            size,
+            last_used: now(),
+        });
+        self.save_index(&index)?;
+
+        self.prune_to(self.max_size, Some(tag))?;
+        Ok(path)
+    }
+
+    /// Where an unfinished download of the archive `name` is kept, so a later run can continue it
+    pub fn partial_path(&self, name: &str) -> Result<PathBuf, InstallerError> {
+        fs::create_dir_all(&self.root)?;
+        Ok(self.root.join(format!("{}.{}", name, PARTIAL_EXTENSION)))
+    }
+
+    /// Remove every cached archive and unfinished download, returning the number of bytes freed
+    pub fn clean(&self) -> Result<u64, InstallerError> {
+        let mut freed: u64 = self.prune_to(0, None)?.iter().map(|entry| entry.size).sum();
+
+        if let Ok(entries) = fs::read_dir(&self.root) {
+            for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+                if path.extension().is_some_and(|extension| extension == PARTIAL_EXTENSION) {
+                    freed += fs::metadata(&path)?.len();
+                    fs::remove_file(&path)?;
+                }
+            }
+        }
+        Ok(freed)
+    }
+
+    /// Evict least recently used archives until the cache fits in `max_size` bytes
+    pub fn prune(&self, max_size: u64) -> Result<Vec<CacheEntry>, InstallerError> {
+        self.prune_to(max_size, None)
+    }
+
+    fn prune_to(&self, max_size: u64, keep_tag: Option<&str>) -> Result<Vec<CacheEntry>, InstallerError> {
+        let mut index = self.load_index()?;
+        index.entries.sort_by_key(|entry| entry.last_used);
+
+        let mut total: u64 = index.entries.iter().map(|entry| entry.size).sum();
+        let mut removed = Vec::new();
+        let mut kept = Vec::new();
+
+        for entry in index.entries.drain(..) {
+            if total > max_size && Some(entry.tag.as_str()) != keep_tag {
+                total -= entry.size;
+                removed.push(entry);
+            } else {
+                kept.push(entry);
+            }
+        }
+        index.entries = kept;
+
+        for entry in &removed {
+            self.remove_blob_if_unused(&index, &entry.sha256)?;
+        }
+        self.save_index(&index)?;
+        Ok(removed)
+    }
+
+    fn remove_blob_if_unused(&self, index: &CacheIndex, sha256: &str) -> Result<(), InstallerError> {
+        let path = self.blob_path(sha256);
+        if path.exists() && !index.entries.iter().any(|entry| entry.sha256 == sha256) {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn blob_path(&self, sha256: &str) -> PathBuf {
+        self.root.join(format!("{}.zip", sha256))
+    }
+
+    fn load_index(&self) -> Result<CacheIndex, InstallerError> {
+        let path = self.root.join(INDEX_FILE_NAME);
+        if !path.exists() {
+            return Ok(CacheIndex::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn save_index(&self, index: &CacheIndex) -> Result<(), InstallerError> {
+        fs::create_dir_all(&self.root)?;
+        fs::write(self.root.join(INDEX_FILE_NAME), serde_json::to_string_pretty(index)?)?;
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
@@ -0,0 +1,204 @@
+use crate::errors::InstallerError;
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+/// How often and how patiently to retry transient network failures
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `attempt` (starting at 1), doubling each time
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// Failure of a single attempt, classified by whether trying again can help
+enum AttemptError {
+    Transient(String),
+    Fatal(InstallerError),
+}
+
+impl AttemptError {
+    /// Writing the download locally failed, e.g. the disk is full; retrying won't help
+    fn local(e: io::Error) -> Self {
+        AttemptError::Fatal(e.into())
+    }
+}
+
+impl From<reqwest::Error> for AttemptError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() || e.is_connect() || e.is_request() || e.is_body() {
+            AttemptError::Transient(e.to_string())
+        } else {
+            AttemptError::Fatal(e.into())
+        }
+    }
+}
+
+/// HTTP downloads that survive dropped connections by resuming with `Range` requests
+pub struct Downloader {
+    client: Client,
+    retry: RetryPolicy,
+}
+
+impl Downloader {
+    pub fn new(client: Client, retry: RetryPolicy) -> Self {
+        Self { client, retry }
+    }
+
+    /// GET `url` as text, retrying transient failures
+    pub fn get_text(&self, url: &str) -> Result<String, InstallerError> {
+        self.with_retries(url, || {
+            let response = self.send(url, None)?;
+            Ok(response.text()?)
+        })
+    }
+
+    /// Download `url` to `output`. A partial file already at `output`, e.g. left by an interrupted
+    /// earlier run, is continued with a `Range` request, as is each retry after a failed attempt.
+    pub fn download(&self, url: &str, output: &Path) -> Result<(), InstallerError> {
+        let pb = ProgressBar::new(0);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+                .map_err(|e| InstallerError::Unknown(e.to_string()))?
+                .progress_chars("#>-"),
+        );
+
+        OpenOptions::new().create(true).append(true).open(output)?;
+        self.with_retries(url, || self.download_attempt(url, output, &pb))?;
+
+        pb.finish_with_message("Download complete");
+        Ok(())
+    }
+
+    fn with_retries<T>(
+        &self,
+        url: &str,
+        mut attempt: impl FnMut() -> Result<T, AttemptError>,
+    ) -> Result<T, InstallerError> {
+        let mut retries = 0;
+        loop {
+            match attempt() {
+                Ok(value) => return Ok(value),
+                Err(AttemptError::Fatal(e)) => return Err(e),
+                Err(AttemptError::Transient(reason)) if retries < self.retry.max_retries => {
+                    retries += 1;
+                    let delay = self.retry.backoff(retries);
+                    println!(
+                        "⚠️ {} — retrying in {:.1}s ({}/{})",
+                        reason, delay.as_secs_f32(), retries, self.retry.max_retries
+                    );
+                    thread::sleep(delay);
+                }
+                Err(AttemptError::Transient(reason)) => {
+                    return Err(InstallerError::Download(format!(
+                        "{} (gave up after {} retries): {}",
+                        url, self.retry.max_retries, reason
+                    )));
+                }
+            }
+        }
+    }
+
+    fn download_attempt(&self, url: &str, output: &Path, pb: &ProgressBar) -> Result<(), AttemptError> {
+        let offset = output.metadata().map_err(AttemptError::local)?.len();
+        let mut response = self.send(url, (offset > 0).then_some(offset))?;
+
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            // The partial file doesn't match what the server has; start over
+            File::create(output).map_err(AttemptError::local)?;
+            return Err(AttemptError::Transient("server refused to resume the download".into()));
+        }
+
+        let resumed = response.status() == StatusCode::PARTIAL_CONTENT;
+        if resumed && content_range_start(&response) != Some(offset) {
+            // Only part of the file, but not the part we're missing; start over
+            File::create(output).map_err(AttemptError::local)?;
+            return Err(AttemptError::Transient("server resumed the download at the wrong offset".into()));
+        }
+        let mut file = if resumed {
+            OpenOptions::new().append(true).open(output).map_err(AttemptError::local)?
+        } else {
+            // Fresh start, or the server ignored the range and sent the whole file
+            File::create(output).map_err(AttemptError::local)?
+        };
+        let mut downloaded = if resumed { offset } else { 0 };
+
+        let expected_total = response.content_length().map(|length| length + downloaded);
+        pb.set_length(expected_total.unwrap_or(0));
+        pb.set_position(downloaded);
+
+        let mut buffer = vec![0; 8192];
+        loop {
+            // Reading the body fails with an I/O error when the connection drops or times out
+            let bytes_read = response.read(&mut buffer).map_err(|e| AttemptError::Transient(e.to_string()))?;
+            if bytes_read == 0 {
+                break;
+            }
+            file.write_all(&buffer[..bytes_read]).map_err(AttemptError::local)?;
+            downloaded += bytes_read as u64;
+            pb.set_position(downloaded);
+        }
+        file.flush().map_err(AttemptError::local)?;
+
+        match expected_total {
+            Some(total) if downloaded < total => Err(AttemptError::Transient(format!(
+                "connection closed after {} of {} bytes", downloaded, total
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn send(&self, url: &str, range_start: Option<u64>) -> Result<Response, AttemptError> {
+        let mut request = self.client.get(url);
+        if let Some(start) = range_start {
+            request = request.header(RANGE, format!("bytes={}-", start));
+        }
+
+        let response = request.send()?;
+        let status = response.status();
+
+        if status.is_success() || (range_start.is_some() && status == StatusCode::RANGE_NOT_SATISFIABLE) {
+            Ok(response)
+        } else if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            Err(AttemptError::Transient(format!("HTTP error {}", status)))
+        } else {
+            Err(AttemptError::Fatal(InstallerError::Download(format!("{}: HTTP error {}", url, status))))
+        }
+    }
+}
+
+/// Start offset from a `Content-Range: bytes <start>-<end>/<total>` header
+fn content_range_start(response: &Response) -> Option<u64> {
+    let value = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    value.strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
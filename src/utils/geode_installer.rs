@@ -1,22 +1,278 @@
 use crate::errors::InstallerError;
-use crate::utils::steam_game_finder::SteamGameFinder;
-use indicatif::{ProgressBar, ProgressStyle};
+use crate::utils::download_cache::DownloadCache;
+use crate::utils::downloader::{Downloader, RetryPolicy};
+use crate::utils::install_manifest::{self, FileProblem, InstallManifest, LaunchOptionsEdit, ManifestFile, RegistryEdit};
+use crate::utils::install_transaction::InstallTransaction;
+use crate::utils::launch_options::{self, LocalConfig};
+use crate::utils::registry_backend::{self, RegistryBackend, RegistryBackendKind};
+use crate::utils::registry_backup::{RegistryBackup, RegistryBackups};
+use crate::utils::steam_game_finder::{GameInfo, SteamGameFinder};
+use crate::utils::wine_processes::{PrefixProcess, WineProcesses};
+use crate::utils::wine_registry;
 use reqwest::blocking::Client;
 use serde_json::Value;
 use std::fs::{self, File};
-use std::io::{self, Read, Write};
+use std::io::{self, Read};
 use std::os::unix::fs::PermissionsExt;
+use std::fmt;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use zip::ZipArchive;
 
 const GD_APP_ID: &str = "322170";
-const GEODE_API_URL: &str = "https://api.geode-sdk.org/v1/loader/versions/latest";
+const GEODE_API_URL: &str = "https://api.geode-sdk.org/v1/loader/versions";
 const GEODE_GITHUB_URL: &str = "https://github.com/geode-sdk/geode/releases/download";
+const GEODE_GITHUB_API_URL: &str = "https://api.github.com/repos/geode-sdk/geode/releases/tags";
+const USER_AGENT: &str = concat!("geode-cli-installer/", env!("CARGO_PKG_VERSION"));
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Applies to each read, so a stalled connection is noticed without limiting total download time
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often to check whether a busy Wine prefix has become idle
+const PREFIX_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How long to wait for Steam when it makes no progress with Geometry Dash, e.g. a queued or paused update
+const STEAM_STALL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const GEODE_DLL: &str = "Geode.dll";
+const GEODE_DATA_DIR: &str = "geode";
+/// User data inside `geode/` that installs and updates must never overwrite
+const PROTECTED_DATA_DIRS: &[&str] = &["geode/mods", "geode/config"];
+const TEMP_ZIP_NAME: &str = "geode_temp.zip";
+const USER_REG: &str = "user.reg";
+const DLL_OVERRIDES_SECTION: &str = "Software\\Wine\\DllOverrides";
+const APP_DLL_OVERRIDES_SECTION: &str = "Software\\Wine\\AppDefaults\\GeometryDash.exe\\DllOverrides";
+const XINPUT_DLL: &str = "xinput1_4";
+const XINPUT_OVERRIDE: &str = "native,builtin";
+/// The same override in the short form used in `WINEDLLOVERRIDES`
+const XINPUT_LAUNCH_OVERRIDE: &str = "n,b";
+
+/// Files the Geode release archive places next to GeometryDash.exe
+const GEODE_LOADER_FILES: &[&str] = &[
+    "Geode.dll",
+    "Geode.pdb",
+    "Geode.lib",
+    "GeodeUpdater.exe",
+    "XInput1_4.dll",
+    "xinput1_4.dll",
+];
 
 pub struct GeodeInstaller {
     finder: SteamGameFinder,
-    client: Client,
+    downloader: Downloader,
+    cache: Option<DownloadCache>,
+    registry: Box<dyn RegistryBackend>,
+    options: InstallOptions,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+    /// Loader tag to install, e.g. `v4.2.0`; the latest release when unset
+    pub version: Option<String>,
+    /// Allow prereleases when resolving the latest release
+    pub prerelease: bool,
+    /// Fail instead of warning when no published checksum is available for the archive
+    pub require_checksum: bool,
+    /// Install from a local `geode-<tag>-win.zip` instead of downloading
+    pub archive: Option<PathBuf>,
+    /// Always download, bypassing the archive cache
+    pub no_cache: bool,
+    /// Cache size limit in bytes, `download_cache::DEFAULT_MAX_SIZE` when unset
+    pub cache_max_size: Option<u64>,
+    /// How many times to retry a failed request, `RetryPolicy::default()` when unset
+    pub retries: Option<u32>,
+    /// Where to put the xinput1_4 override; when unset, Proton prefixes get a prefix-wide
+    /// override and custom Wine prefixes one for Geometry Dash only
+    pub override_scope: Option<OverrideScope>,
+    /// Backups kept per registry file, `registry_backup::DEFAULT_KEEP` when unset
+    pub registry_backups: Option<usize>,
+    /// Wait for programs running in the Wine prefix to exit instead of failing
+    pub wait: bool,
+    /// How to read and write the Wine registry
+    pub registry_backend: RegistryBackendKind,
+    /// `wine` binary for `RegistryBackendKind::Wine`, `wine` from `PATH` when unset
+    pub wine_binary: Option<PathBuf>,
+    /// Set the xinput1_4 override in Geometry Dash's Steam launch options instead of the
+    /// Wine registry, which Proton may recreate when its version changes
+    pub launch_options: bool,
+    /// Steam installation to use, searched for when unset
+    pub steam_root: Option<PathBuf>,
+    /// Where to look for running Wine programs and Steam, `/proc` when unset
+    pub proc_root: Option<PathBuf>,
+    /// With `wait`, give up on Steam once its state and download progress stay unchanged
+    /// this long, `STEAM_STALL_TIMEOUT` when unset
+    pub steam_stall_timeout: Option<Duration>,
+}
+
+/// Which programs in the Wine prefix the xinput1_4 override applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverrideScope {
+    /// `[Software\\Wine\\DllOverrides]`: every program in the prefix
+    Prefix,
+    /// `[Software\\Wine\\AppDefaults\\GeometryDash.exe\\DllOverrides]`: only Geometry Dash
+    App,
+}
+
+impl OverrideScope {
+    fn section(self) -> &'static str {
+        match self {
+            OverrideScope::Prefix => DLL_OVERRIDES_SECTION,
+            OverrideScope::App => APP_DLL_OVERRIDES_SECTION,
+        }
+    }
+}
+
+impl fmt::Display for OverrideScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverrideScope::Prefix => write!(f, "prefix"),
+            OverrideScope::App => write!(f, "app"),
+        }
+    }
+}
+
+impl FromStr for OverrideScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "prefix" => Ok(OverrideScope::Prefix),
+            "app" => Ok(OverrideScope::App),
+            _ => Err(format!("expected 'prefix' or 'app', got '{}'", s)),
+        }
+    }
+}
+
+/// The xinput1_4 override Wine applies to Geometry Dash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DllOverride {
+    pub scope: OverrideScope,
+    pub value: String,
+}
+
+impl DllOverride {
+    /// Whether Wine tries Geode's native xinput1_4.dll before its builtin one.
+    /// `builtin`, `builtin,native` and an empty (disabled) override all keep Geode from loading.
+    pub fn loads_native(&self) -> bool {
+        loads_native(&self.value)
+    }
+}
+
+/// Published size and SHA-256 of a release archive
+#[derive(Debug, Clone)]
+pub struct ArchiveDigest {
+    pub sha256: String,
+    pub size: Option<u64>,
+}
+
+impl ArchiveDigest {
+    /// Check a downloaded archive against the digest, deleting it if it doesn't match
+    /// so a corrupt or tampered download is never extracted or cached
+    pub fn verify(&self, zip_path: &Path) -> Result<(), InstallerError> {
+        let result = self.compare(zip_path);
+        if result.is_err() {
+            let _ = fs::remove_file(zip_path);
+        }
+        result
+    }
+
+    fn compare(&self, zip_path: &Path) -> Result<(), InstallerError> {
+        let size = fs::metadata(zip_path)?.len();
+        if let Some(expected) = self.size
+            && expected != size
+        {
+            return Err(InstallerError::ChecksumMismatch {
+                expected: format!("{} bytes", expected),
+                actual: format!("{} bytes", size),
+            });
+        }
+
+        let actual = install_manifest::sha256_file(zip_path)?;
+        if !actual.eq_ignore_ascii_case(&self.sha256) {
+            return Err(InstallerError::ChecksumMismatch {
+                expected: format!("sha256:{}", self.sha256),
+                actual: format!("sha256:{}", actual),
+            });
+        }
+
+        println!("Checksum verified: sha256:{}", actual);
+        Ok(())
+    }
+}
+
+/// Installed and available loader versions for a game directory
+#[derive(Debug, Clone)]
+pub struct UpdateCheck {
+    /// Tag recorded in the install manifest, `None` if Geode was installed without one
+    pub installed: Option<String>,
+    pub available: String,
+    /// Cached archive of `available`, if resolving it already looked that up
+    cached: Option<PathBuf>,
+}
+
+impl UpdateCheck {
+    /// Never true without a manifest, so such installs are updated once to record one
+    pub fn is_up_to_date(&self) -> bool {
+        self.installed.as_deref() == Some(self.available.as_str())
+    }
+}
+
+/// Loader files touched by an update, relative to the game directory
+#[derive(Debug, Default, PartialEq)]
+pub struct UpdateSummary {
+    pub added: Vec<PathBuf>,
+    pub changed: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+}
+
+impl UpdateSummary {
+    fn between(installed: &[ManifestFile], release: &[ManifestFile]) -> Self {
+        let mut summary = Self::default();
+        for file in release {
+            match installed.iter().find(|old| old.path == file.path) {
+                None => summary.added.push(file.path.clone()),
+                Some(old) if old.sha256 != file.sha256 => summary.changed.push(file.path.clone()),
+                Some(_) => {}
+            }
+        }
+        summary.removed = installed.iter()
+            .filter(|old| !release.iter().any(|file| file.path == old.path))
+            .map(|old| old.path.clone())
+            .collect();
+        summary
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A release archive fetched for an update and compared with the installed files,
+/// so the changes can be shown before anything is replaced
+#[derive(Debug)]
+pub struct UpdatePlan {
+    pub tag: String,
+    pub summary: UpdateSummary,
+    archive: PathBuf,
+    /// Downloaded for this update only, deleted along with the plan
+    temporary: bool,
+}
+
+impl Drop for UpdatePlan {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_file(&self.archive);
+        }
+    }
+}
+
+/// A Geode loader release as listed by the Geode API
+#[derive(Debug, Clone)]
+pub struct LoaderRelease {
+    pub tag: String,
+    pub prerelease: bool,
+    pub created_at: Option<String>,
+    /// Geometry Dash version supported on Windows
+    pub gd_version: Option<String>,
 }
 
 #[derive(Debug)]
@@ -25,24 +281,94 @@ pub struct InstallationPaths {
     pub proton_prefix: PathBuf,
 }
 
+#[derive(Debug)]
+pub struct InstallationStatus {
+    pub geode_installed: bool,
+    /// The per-application override if there is one, since Wine prefers it over the prefix-wide one
+    pub dll_override: Option<DllOverride>,
+    /// The xinput1_4 mode set by `WINEDLLOVERRIDES` in Geometry Dash's Steam launch options,
+    /// which takes precedence over the registry
+    pub launch_option_override: Option<String>,
+    pub manifest: Option<InstallManifest>,
+}
+
 impl GeodeInstaller {
     pub fn new() -> Result<Self, InstallerError> {
+        Self::with_options(InstallOptions::default())
+    }
+
+    pub fn with_options(options: InstallOptions) -> Result<Self, InstallerError> {
         let client = Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(READ_TIMEOUT)
             .build()?;
 
+        let mut retry = RetryPolicy::default();
+        if let Some(retries) = options.retries {
+            retry.max_retries = retries;
+        }
+
+        let cache = if options.no_cache {
+            None
+        } else {
+            DownloadCache::new().map(|cache| match options.cache_max_size {
+                Some(max_size) => cache.with_max_size(max_size),
+                None => cache,
+            })
+        };
+
         Ok(Self {
-            finder: SteamGameFinder::new(),
-            client,
+            finder: match &options.steam_root {
+                Some(steam_root) => SteamGameFinder::at(steam_root.clone()),
+                None => SteamGameFinder::new(),
+            },
+            downloader: Downloader::new(client, retry),
+            cache,
+            registry: registry_backend::backend(options.registry_backend, options.wine_binary.clone()),
+            options,
         })
     }
 
+    /// List loader releases available from the Geode API, newest first
+    pub fn list_versions(&self, include_prereleases: bool) -> Result<Vec<LoaderRelease>, InstallerError> {
+        let url = format!("{}?prerelease={}&per_page=100", GEODE_API_URL, include_prereleases);
+        parse_releases(&self.fetch_api_payload(&url)?, include_prereleases)
+    }
+
+    /// Resolve the loader tag that an installation with the current options would use
+    pub fn resolve_version(&self) -> Result<String, InstallerError> {
+        self.resolve_release().map(|(tag, _)| tag)
+    }
+
+    /// The loader tag to install, with its cached archive when looking that up was needed
+    /// anyway, so installing doesn't hash the archive again
+    fn resolve_release(&self) -> Result<(String, Option<PathBuf>), InstallerError> {
+        if let Some(archive) = &self.options.archive {
+            return Ok((self.local_archive_tag(archive)?, None));
+        }
+
+        match &self.options.version {
+            Some(version) => {
+                let tag = normalize_tag(version);
+                if let Some(cached) = self.cached_archive(&tag) {
+                    println!("Using cached archive: {:?}", cached);
+                    return Ok((tag, Some(cached))); // Known good, no need to ask the API
+                }
+                Ok((self.fetch_tag(&tag)?, None))
+            }
+            None => Ok((self.fetch_latest_tag()?, None)),
+        }
+    }
+
     /// Install Geode to Steam's Geometry Dash installation
     pub fn install_to_steam(&self) -> Result<(), InstallerError> {
-        let steam_root = self.finder.steam_root()
+        let steam = self.finder.installation()
             .ok_or_else(|| InstallerError::Installation("Can't find Steam installation".into()))?;
 
-        println!("Steam root found at: {:?}", steam_root);
+        println!("Steam root found at: {:?} ({})", steam.root, steam.packaging);
 
+        self.ensure_game_ready()?;
         let paths = self.locate_geometry_dash()?;
 
         println!("Geometry Dash found at: {:?}", paths.game_path);
@@ -57,18 +383,296 @@ impl GeodeInstaller {
     pub fn install_to_wine(&self, prefix: &Path, game_dir: &Path) -> Result<(), InstallerError> {
         self.validate_paths(prefix, game_dir)?;
 
-        println!("Installing Geode to: {:?}", game_dir);
-        self.install_to_directory(game_dir)?;
+        let (tag, cached) = self.resolve_release()?;
+        self.install_release(prefix, game_dir, &tag, cached.as_deref())?;
+        Ok(())
+    }
+
+    /// The xinput1_4 override in `prefix` if it would keep Wine from loading Geode.
+    ///
+    /// Installing rewrites such an override to `native,builtin`; front ends use this
+    /// beforehand to explain the change and ask for confirmation.
+    pub fn conflicting_dll_override(&self, prefix: &Path) -> Result<Option<DllOverride>, InstallerError> {
+        if !prefix.is_dir() {
+            // Nothing to check; installing reports the missing prefix
+            return Ok(None);
+        }
 
-        println!("Patching Wine registry...");
-        self.patch_wine_registry(prefix)?;
+        // Reading through `wine reg` starts a wineserver, which would leave the prefix looking busy
+        // to the install that follows. Refuse a busy prefix now, and let our wineserver exit after.
+        self.ensure_prefix_idle(prefix)?;
+        let dll_override = read_dll_override(self.registry.as_ref(), prefix)?;
+        self.registry.flush(prefix)?;
+        Ok(dll_override.filter(|dll_override| !dll_override.loads_native()))
+    }
+
+    /// Compare the installed loader version with the one the current options resolve to,
+    /// without downloading anything
+    pub fn check_update(&self, game_dir: &Path) -> Result<UpdateCheck, InstallerError> {
+        let manifest = InstallManifest::load(game_dir)?;
+        if manifest.is_none() && !game_dir.join(GEODE_DLL).exists() {
+            return Err(InstallerError::Installation(format!(
+                "Geode is not installed in {:?}",
+                game_dir
+            )));
+        }
+
+        let (available, cached) = self.resolve_release()?;
+        Ok(UpdateCheck {
+            installed: manifest.map(|manifest| manifest.tag),
+            available,
+            cached,
+        })
+    }
+
+    /// Fetch the release from `check` and work out which loader files updating would add,
+    /// change and remove. Without a manifest the previously installed files are unknown, so
+    /// only files the release ships are compared and nothing is reported as removed.
+    pub fn plan_update(&self, game_dir: &Path, check: &UpdateCheck) -> Result<UpdatePlan, InstallerError> {
+        let (archive, temporary) = match &check.cached {
+            Some(cached) => (cached.clone(), false),
+            None => self.fetch_archive(game_dir, &check.available)?,
+        };
+        // Owning the archive from here on deletes a temporary download if comparing fails
+        let mut plan = UpdatePlan {
+            tag: check.available.clone(),
+            summary: UpdateSummary::default(),
+            archive,
+            temporary,
+        };
+
+        let release = self.release_files(&plan.archive)?;
+        let installed = match InstallManifest::load(game_dir)? {
+            Some(manifest) => manifest.files,
+            None => release.iter()
+                .filter(|file| game_dir.join(&file.path).is_file())
+                .map(|file| ManifestFile::record(game_dir, &file.path, file.crc32))
+                .collect::<Result<_, _>>()?,
+        };
+
+        plan.summary = UpdateSummary::between(&installed, &release);
+        Ok(plan)
+    }
+
+    /// Replace the loader files with the release from `plan`, leaving mods, settings and saves alone
+    pub fn apply_update(&self, prefix: &Path, game_dir: &Path, plan: &UpdatePlan) -> Result<(), InstallerError> {
+        self.validate_paths(prefix, game_dir)?;
+        self.install_release(prefix, game_dir, &plan.tag, Some(&plan.archive))?;
+        Ok(())
+    }
+
+    /// Install `tag` as one transaction: if any step fails, the game directory and
+    /// `user.reg` are put back the way they were. `archive` is one already fetched for `tag`.
+    fn install_release(
+        &self,
+        prefix: &Path,
+        game_dir: &Path,
+        tag: &str,
+        archive: Option<&Path>,
+    ) -> Result<InstallManifest, InstallerError> {
+        let previous = InstallManifest::load(game_dir)?;
+
+        self.ensure_prefix_idle(prefix)?;
+        if self.options.launch_options {
+            self.ensure_steam_closed()?;
+        }
+
+        println!("Installing Geode {} to: {:?}", tag, game_dir);
+        let mut transaction = InstallTransaction::begin(game_dir)?;
+        let manifest = match self.install_staged(prefix, game_dir, tag, archive, previous.as_ref(), &mut transaction) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                println!("Installation failed, rolling back...");
+                if let Err(rollback_error) = transaction.rollback() {
+                    println!("⚠️ Rollback incomplete: {}", rollback_error);
+                }
+                return Err(e);
+            }
+        };
+        transaction.commit()?;
+
+        if let Some(previous) = &previous {
+            // Stale files are gone by now; this only clears the directories they leave empty
+            self.remove_installed_files(game_dir, Self::stale_files(previous, &manifest.files))?;
+        }
 
         println!("Geode installation completed!");
+        Ok(manifest)
+    }
+
+    fn install_staged(
+        &self,
+        prefix: &Path,
+        game_dir: &Path,
+        tag: &str,
+        archive: Option<&Path>,
+        previous: Option<&InstallManifest>,
+        transaction: &mut InstallTransaction,
+    ) -> Result<InstallManifest, InstallerError> {
+        let staging_dir = transaction.staging_dir();
+        let files = match archive {
+            Some(archive) => {
+                fs::create_dir_all(&staging_dir)?;
+                self.extract_zip(archive, &staging_dir)?
+            }
+            None => self.install_to_directory(&staging_dir, tag)?,
+        };
+
+        for file in &files {
+            transaction.place(&file.path)?;
+        }
+        if let Some(previous) = previous {
+            for file in Self::stale_files(previous, &files) {
+                transaction.remove(&file.path)?;
+            }
+        }
+
+        // The download may have given the user time to start the game
+        self.ensure_prefix_idle(prefix)?;
+
+        // Edits from earlier runs are kept, even in the other mode, so uninstalling can still revert them
+        let mut registry = previous.map(|manifest| manifest.registry.clone()).unwrap_or_default();
+        let mut launch_options = previous.map(|manifest| manifest.launch_options.clone()).unwrap_or_default();
+
+        if self.options.launch_options {
+            println!("Setting Steam launch options...");
+            for edit in self.set_launch_options(transaction)? {
+                launch_options.retain(|old| old.config != edit.config);
+                launch_options.push(edit);
+            }
+        } else {
+            println!("Patching Wine registry...");
+            transaction.snapshot(&prefix.join(USER_REG))?;
+            if let Some(mut edit) = self.patch_wine_registry(prefix)? {
+                let earlier = registry.iter().position(|old| {
+                    old.file == edit.file && edit_section(old) == edit_section(&edit) && old.name == edit.name
+                });
+                // Uninstalling restores what was there before the first install, not our own earlier value
+                if let Some(position) = earlier {
+                    let old = registry.remove(position);
+                    edit.previous = old.previous;
+                    edit.created_section = old.created_section;
+                }
+                registry.push(edit);
+            }
+        }
+
+        let manifest = InstallManifest {
+            tag: tag.to_string(),
+            installed_at: current_timestamp(),
+            game_dir: game_dir.to_path_buf(),
+            prefix: prefix.to_path_buf(),
+            files,
+            registry,
+            launch_options,
+        };
+        transaction.snapshot(&InstallManifest::path_in(game_dir))?;
+        manifest.save()?;
+
+        Ok(manifest)
+    }
+
+    /// Files shipped by the old release but not the new one
+    fn stale_files<'a>(previous: &'a InstallManifest, files: &[ManifestFile]) -> Vec<&'a ManifestFile> {
+        previous.files.iter()
+            .filter(|old| !files.iter().any(|file| file.path == old.path))
+            .collect()
+    }
+
+    /// Remove Geode from Steam's Geometry Dash installation
+    pub fn uninstall_from_steam(&self, keep_data: bool) -> Result<(), InstallerError> {
+        let paths = self.locate_geometry_dash()?;
+
+        println!("Geometry Dash found at: {:?}", paths.game_path);
+        println!("Proton prefix found at: {:?}", paths.proton_prefix);
+
+        self.uninstall_from_wine(&paths.proton_prefix, &paths.game_path, keep_data)
+    }
+
+    /// Remove Geode from a custom Wine prefix and game directory.
+    ///
+    /// The `geode/` folder with mods and settings is only deleted when `keep_data` is false.
+    pub fn uninstall_from_wine(&self, prefix: &Path, game_dir: &Path, keep_data: bool) -> Result<(), InstallerError> {
+        self.validate_paths(prefix, game_dir)?;
+        self.ensure_prefix_idle(prefix)?;
+
+        let manifest = InstallManifest::load(game_dir)?;
+        let launch_options = manifest.as_ref().map(|manifest| manifest.launch_options.as_slice()).unwrap_or_default();
+        if !launch_options.is_empty() {
+            self.ensure_steam_closed()?;
+        }
+
+        println!("Removing Geode from: {:?}", game_dir);
+        match &manifest {
+            Some(manifest) => self.remove_installed_files(game_dir, &manifest.files)?,
+            None => self.remove_loader_files(game_dir)?,
+        }
+
+        let temp_zip = game_dir.join(TEMP_ZIP_NAME);
+        if temp_zip.is_file() {
+            fs::remove_file(&temp_zip)?;
+        }
+
+        let data_dir = game_dir.join(GEODE_DATA_DIR);
+        if !keep_data && data_dir.exists() {
+            println!("Removing mods and settings: {:?}", data_dir);
+            fs::remove_dir_all(&data_dir)?;
+        }
+
+        println!("Reverting Wine registry...");
+        match &manifest {
+            Some(manifest) => self.unpatch_wine_registry(prefix, &manifest.registry)?,
+            // Installed by an older version or by hand: assume the section was ours if nothing else is in it
+            None => self.unpatch_wine_registry(prefix, &[
+                dll_override_edit(OverrideScope::Prefix, true),
+                dll_override_edit(OverrideScope::App, true),
+            ])?,
+        }
+
+        if !launch_options.is_empty() {
+            println!("Reverting Steam launch options...");
+            self.unset_launch_options(launch_options)?;
+        }
+
+        InstallManifest::remove(game_dir)?;
+
+        println!("Geode has been removed!");
         Ok(())
     }
 
+    /// The xinput1_4 override as seen through `backend` rather than the configured one,
+    /// e.g. to check that our edits of `user.reg` match what Wine itself reports
+    pub fn dll_override_from(&self, backend: &dyn RegistryBackend, prefix: &Path) -> Result<Option<DllOverride>, InstallerError> {
+        read_dll_override(backend, prefix)
+    }
+
+    /// Inspect a game directory and Wine prefix for an existing Geode installation
+    pub fn status(&self, prefix: &Path, game_dir: &Path) -> Result<InstallationStatus, InstallerError> {
+        self.validate_paths(prefix, game_dir)?;
 
-    fn locate_geometry_dash(&self) -> Result<InstallationPaths, InstallerError> {
+        Ok(InstallationStatus {
+            geode_installed: game_dir.join(GEODE_DLL).exists(),
+            dll_override: read_dll_override(self.registry.as_ref(), prefix)?,
+            launch_option_override: self.launch_option_override()?,
+            manifest: InstallManifest::load(game_dir)?,
+        })
+    }
+
+    /// Read the manifest written by the last installation into `game_dir`
+    pub fn manifest(&self, game_dir: &Path) -> Result<Option<InstallManifest>, InstallerError> {
+        InstallManifest::load(game_dir)
+    }
+
+    /// Check the installed Geode files against the manifest
+    pub fn verify(&self, game_dir: &Path) -> Result<Vec<FileProblem>, InstallerError> {
+        let manifest = InstallManifest::load(game_dir)?.ok_or_else(|| {
+            InstallerError::Verification(format!("No installation manifest found in {:?}", game_dir))
+        })?;
+        manifest.verify()
+    }
+
+    /// Locate Steam's Geometry Dash installation and its Proton prefix
+    pub fn locate_geometry_dash(&self) -> Result<InstallationPaths, InstallerError> {
         let game_info = self.finder.get_game_info(GD_APP_ID)
             .ok_or_else(|| InstallerError::Installation("Can't find Geometry Dash installation".into()))?;
 
@@ -81,6 +685,70 @@ impl GeodeInstaller {
         })
     }
 
+    /// Put a registry backup back in place once nothing runs in the prefix, since the
+    /// wineserver would otherwise overwrite the restored file when it exits
+    pub fn restore_registry_backup(&self, prefix: &Path, backup: &RegistryBackup) -> Result<(), InstallerError> {
+        self.ensure_prefix_idle(prefix)?;
+        RegistryBackups::in_prefix(prefix).restore(backup)
+    }
+
+    /// Steam's record of Geometry Dash, including the Proton version it runs with
+    pub fn steam_game_info(&self) -> Option<GameInfo> {
+        self.finder.get_game_info(GD_APP_ID)
+    }
+
+    /// Make sure Steam isn't downloading, updating or validating Geometry Dash, since it would
+    /// overwrite what we install. Waits for Steam to finish when the `wait` option is set, for
+    /// as long as it makes progress.
+    pub fn ensure_game_ready(&self) -> Result<(), InstallerError> {
+        let stall_timeout = self.options.steam_stall_timeout.unwrap_or(STEAM_STALL_TIMEOUT);
+        let mut waiting = false;
+        let mut progress = None;
+        let mut last_progress = Instant::now();
+
+        loop {
+            let game_info = self.finder.get_game_info(GD_APP_ID)
+                .ok_or_else(|| InstallerError::Installation("Can't find Geometry Dash installation".into()))?;
+            if game_info.state_flags.is_fully_installed() {
+                return Ok(());
+            }
+
+            let mut state = format!("Steam reports it as {}", game_info.state_flags);
+            if game_info.bytes_to_download > 0 {
+                state.push_str(&format!(
+                    ", {} of {} bytes downloaded",
+                    game_info.bytes_downloaded, game_info.bytes_to_download
+                ));
+            }
+
+            if !self.options.wait {
+                return Err(InstallerError::GameNotReady(format!(
+                    "{}. Let Steam finish installing or updating Geometry Dash, then try again.",
+                    state
+                )));
+            }
+
+            let current = (game_info.state_flags, game_info.bytes_downloaded);
+            if progress != Some(current) {
+                progress = Some(current);
+                last_progress = Instant::now();
+            } else if last_progress.elapsed() >= stall_timeout {
+                return Err(InstallerError::GameNotReady(format!(
+                    "{}, with no progress for {} seconds. The update may be queued or paused; \
+                     start it from Steam's Downloads page, then try again.",
+                    state,
+                    stall_timeout.as_secs()
+                )));
+            }
+
+            if !waiting {
+                println!("Waiting for Steam to finish with Geometry Dash ({})...", state);
+                waiting = true;
+            }
+            thread::sleep(PREFIX_POLL_INTERVAL);
+        }
+    }
+
     fn validate_paths(&self, prefix: &Path, game_dir: &Path) -> Result<(), InstallerError> {
         if !prefix.exists() {
             return Err(InstallerError::Unknown(format!(
@@ -97,100 +765,221 @@ impl GeodeInstaller {
         Ok(())
     }
 
-    fn install_to_directory(&self, destination: &Path) -> Result<(), InstallerError> {
-        let download_url = self.get_download_url()?;
-        println!("Downloading Geode...");
-        self.download_and_extract(&download_url, destination)?;
-        Ok(())
-    }
+    fn install_to_directory(&self, destination: &Path, tag: &str) -> Result<Vec<ManifestFile>, InstallerError> {
+        fs::create_dir_all(destination)?;
+        let (archive, temporary) = self.fetch_archive(destination, tag)?;
+        let files = self.extract_zip(&archive, destination);
 
-    fn get_download_url(&self) -> Result<String, InstallerError> {
-        let tag = self.fetch_latest_tag()?;
-        Ok(format!("{}/{}/geode-{}-win.zip", GEODE_GITHUB_URL, tag, tag))
+        if temporary && archive.exists() {
+            fs::remove_file(&archive)?;
+        }
+        files
     }
 
-    fn fetch_latest_tag(&self) -> Result<String, InstallerError> {
-        let response = self.http_get(GEODE_API_URL)?;
-        let json: Value = serde_json::from_str(&response)?;
+    /// The archive for `tag`: the local one, a cached copy, or a verified download, which goes
+    /// into the cache when there is one and `dir` otherwise. Downloads that can't be cached are
+    /// temporary, which the caller deletes when done.
+    fn fetch_archive(&self, dir: &Path, tag: &str) -> Result<(PathBuf, bool), InstallerError> {
+        if let Some(archive) = &self.options.archive {
+            println!("Extracting local archive: {:?}", archive);
+            return Ok((archive.clone(), false));
+        }
+
+        if let Some(cached) = self.cached_archive(tag) {
+            println!("Using cached archive: {:?}", cached);
+            return Ok((cached, false));
+        }
 
-        if let Some(error) = json["error"].as_str() {
-            if !error.is_empty() {
-                return Err(InstallerError::Unknown(format!("Geode API error: {}", error)));
+        let download_url = self.get_download_url(tag);
+        let digest = self.fetch_archive_digest(tag)?;
+        println!("Downloading Geode {}...", tag);
+        self.download_archive(&download_url, dir, tag, digest.as_ref())
+    }
+
+    fn cached_archive(&self, tag: &str) -> Option<PathBuf> {
+        let cache = self.cache.as_ref()?;
+        match cache.lookup(tag) {
+            Ok(path) => path,
+            Err(e) => {
+                println!("⚠️ Ignoring download cache: {}", e);
+                None
             }
         }
+    }
 
-        json["payload"]["tag"]
-            .as_str()
-            .map(String::from)
-            .ok_or_else(|| InstallerError::Unknown("Failed to extract version tag from API response".into()))
+    fn get_download_url(&self, tag: &str) -> String {
+        format!("{}/{}/{}", GEODE_GITHUB_URL, tag, archive_name(tag))
     }
 
-    fn download_and_extract(&self, url: &str, destination: &Path) -> Result<(), InstallerError> {
-        fs::create_dir_all(destination)?;
+    /// Look up the archive's digest in the GitHub release asset metadata
+    fn fetch_archive_digest(&self, tag: &str) -> Result<Option<ArchiveDigest>, InstallerError> {
+        let digest = self.http_get(&format!("{}/{}", GEODE_GITHUB_API_URL, tag))
+            .and_then(|response| Ok(serde_json::from_str::<Value>(&response)?))
+            .map(|release| parse_asset_digest(&release, &archive_name(tag)));
 
-        let zip_path = destination.join("geode_temp.zip");
+        match digest {
+            Ok(Some(digest)) => Ok(Some(digest)),
+            Ok(None) if self.options.require_checksum => Err(InstallerError::Installation(format!(
+                "No checksum is published for {}", archive_name(tag)
+            ))),
+            Err(e) if self.options.require_checksum => Err(InstallerError::Installation(format!(
+                "Failed to fetch checksum for {}: {}", archive_name(tag), e
+            ))),
+            _ => {
+                println!("⚠️ No published checksum for {}, skipping verification", archive_name(tag));
+                Ok(None)
+            }
+        }
+    }
 
-        self.download_file(url, &zip_path)?;
-        self.extract_zip(&zip_path, destination)?;
+    /// Check that `archive` is a Geode release and work out its tag, from the file name
+    /// or else the `version` option
+    fn local_archive_tag(&self, archive: &Path) -> Result<String, InstallerError> {
+        let invalid = |reason: &str| InstallerError::Installation(format!(
+            "{:?} is not a Geode release archive: {}", archive, reason
+        ));
 
-        fs::remove_file(&zip_path)?;
+        let file = File::open(archive)?;
+        let zip = ZipArchive::new(file).map_err(|e| invalid(&e.to_string()))?;
+        for required in [GEODE_DLL, "XInput1_4.dll"] {
+            if !zip.file_names().any(|name| name.eq_ignore_ascii_case(required)) {
+                return Err(invalid(&format!("{} is missing", required)));
+            }
+        }
 
-        Ok(())
+        let from_name = archive.file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("geode-"))
+            .and_then(|name| name.strip_suffix("-win.zip"))
+            .map(normalize_tag);
+
+        from_name
+            .or_else(|| self.options.version.as_deref().map(normalize_tag))
+            .ok_or_else(|| InstallerError::Installation(format!(
+                "Can't tell the Geode version of {:?}; name it geode-<tag>-win.zip or pass the version explicitly",
+                archive
+            )))
     }
 
+    fn fetch_latest_tag(&self) -> Result<String, InstallerError> {
+        let url = format!("{}/latest?prerelease={}", GEODE_API_URL, self.options.prerelease);
+        self.fetch_release_tag(&url)
+    }
 
-    fn http_get(&self, url: &str) -> Result<String, InstallerError> {
-        let response = self.client.get(url).send()?;
+    fn fetch_tag(&self, tag: &str) -> Result<String, InstallerError> {
+        self.fetch_release_tag(&format!("{}/{}", GEODE_API_URL, tag))
+            .map_err(|e| InstallerError::Installation(format!("Geode {} is not available: {}", tag, e)))
+    }
 
-        if !response.status().is_success() {
-            return Err(InstallerError::Unknown(format!("HTTP error {}", response.status())));
+    fn fetch_release_tag(&self, url: &str) -> Result<String, InstallerError> {
+        self.fetch_api_payload(url)?["tag"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| InstallerError::Unknown("Failed to extract version tag from API response".into()))
+    }
+
+    fn fetch_api_payload(&self, url: &str) -> Result<Value, InstallerError> {
+        let response = self.http_get(url)?;
+        let mut json: Value = serde_json::from_str(&response)?;
+
+        if let Some(error) = json["error"].as_str()
+            && !error.is_empty()
+        {
+            return Err(InstallerError::Unknown(format!("Geode API error: {}", error)));
         }
 
-        Ok(response.text()?)
+        Ok(json["payload"].take())
     }
 
+    fn download_archive(
+        &self,
+        url: &str,
+        dir: &Path,
+        tag: &str,
+        digest: Option<&ArchiveDigest>,
+    ) -> Result<(PathBuf, bool), InstallerError> {
+        // Downloads kept in the cache directory survive an interrupted run and are continued
+        // by the next one; elsewhere they start from scratch
+        let zip_path = match &self.cache {
+            Some(cache) => cache.partial_path(&archive_name(tag))?,
+            None => {
+                fs::create_dir_all(dir)?;
+                let zip_path = dir.join(TEMP_ZIP_NAME);
+                if zip_path.exists() {
+                    fs::remove_file(&zip_path)?;
+                }
+                zip_path
+            }
+        };
+        if let Ok(metadata) = fs::metadata(&zip_path)
+            && metadata.len() > 0
+        {
+            println!("Continuing an interrupted download ({} bytes so far)", metadata.len());
+        }
 
-    fn download_file(&self, url: &str, output: &Path) -> Result<(), InstallerError> {
-        let mut response = self.client.get(url).send()?;
-        if !response.status().is_success() {
-            return Err(InstallerError::Unknown(format!("HTTP error {}", response.status())));
-        }
-
-        let total_size = response.content_length().unwrap_or(0);
-        let pb = ProgressBar::new(total_size);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-                .map_err(|e| InstallerError::Unknown(e.to_string()))?
-                .progress_chars("#>-"),
-        );
-
-        let mut file = File::create(output)?;
-        let mut downloaded = 0u64;
-        let mut buffer = vec![0; 8192];
+        self.download_file(url, &zip_path)?;
+        if let Some(digest) = digest {
+            digest.verify(&zip_path)?;
+        }
 
-        loop {
-            let bytes_read = response.read(&mut buffer)?;
-            if bytes_read == 0 {
-                break;
+        let Some(cache) = &self.cache else {
+            return Ok((zip_path, true));
+        };
+        match cache.store(tag, &zip_path) {
+            Ok(cached) => {
+                if zip_path.exists() {
+                    fs::remove_file(&zip_path)?;
+                }
+                Ok((cached, false))
+            }
+            Err(e) => {
+                println!("⚠️ Failed to cache the archive: {}", e);
+                Ok((zip_path, true))
             }
-            file.write_all(&buffer[..bytes_read])?;
-            downloaded += bytes_read as u64;
-            pb.set_position(downloaded);
         }
+    }
 
-        pb.finish_with_message("Download complete");
-        Ok(())
+    fn http_get(&self, url: &str) -> Result<String, InstallerError> {
+        self.downloader.get_text(url)
+    }
+
+    fn download_file(&self, url: &str, output: &Path) -> Result<(), InstallerError> {
+        self.downloader.download(url, output)
     }
 
-    fn extract_zip(&self, zip_path: &Path, destination: &Path) -> Result<(), InstallerError> {
+    fn extract_zip(&self, zip_path: &Path, destination: &Path) -> Result<Vec<ManifestFile>, InstallerError> {
         let file = File::open(zip_path)?;
         let mut archive = ZipArchive::new(file)?;
 
+        let mut files = Vec::new();
         for i in 0..archive.len() {
-            self.extract_zip_entry(&mut archive, i, destination)?;
+            files.extend(self.extract_zip_entry(&mut archive, i, destination)?);
         }
-        Ok(())
+        Ok(files)
+    }
+
+    /// What extracting `zip_path` would install, without writing anything
+    fn release_files(&self, zip_path: &Path) -> Result<Vec<ManifestFile>, InstallerError> {
+        let mut archive = ZipArchive::new(File::open(zip_path)?)?;
+
+        let mut files = Vec::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            let Some(path) = file.enclosed_name() else {
+                continue;
+            };
+            if file.is_dir() || PROTECTED_DATA_DIRS.iter().any(|dir| path.starts_with(dir)) {
+                continue;
+            }
+
+            files.push(ManifestFile {
+                sha256: install_manifest::sha256_reader(&mut file)?,
+                path,
+                size: file.size(),
+                crc32: file.crc32(),
+            });
+        }
+        Ok(files)
     }
 
     fn extract_zip_entry(
@@ -198,14 +987,19 @@ impl GeodeInstaller {
         archive: &mut ZipArchive<File>,
         index: usize,
         destination: &Path,
-    ) -> Result<(), InstallerError> {
+    ) -> Result<Option<ManifestFile>, InstallerError> {
         let mut file = archive.by_index(index)?;
-        let out_path = match file.enclosed_name() {
-            Some(path) => destination.join(path),
-            None => return Ok(()), // Skip unsafe paths
+        let relative_path = match file.enclosed_name() {
+            Some(path) => path,
+            None => return Ok(None), // Skip unsafe paths
         };
+        if PROTECTED_DATA_DIRS.iter().any(|dir| relative_path.starts_with(dir)) {
+            return Ok(None);
+        }
+        let out_path = destination.join(&relative_path);
 
-        if file.name().ends_with('/') {
+        let is_dir = file.name().ends_with('/');
+        if is_dir {
             fs::create_dir_all(&out_path)?;
         } else {
             self.extract_file(&mut file, &out_path)?;
@@ -216,7 +1010,10 @@ impl GeodeInstaller {
             fs::set_permissions(&out_path, fs::Permissions::from_mode(mode))?;
         }
 
-        Ok(())
+        if is_dir {
+            return Ok(None);
+        }
+        Ok(Some(ManifestFile::record(destination, &relative_path, file.crc32())?))
     }
 
     fn extract_file(&self, zip_file: &mut dyn Read, out_path: &Path) -> Result<(), InstallerError> {
@@ -228,57 +1025,266 @@ impl GeodeInstaller {
         Ok(())
     }
 
-    fn patch_wine_registry(&self, prefix: &Path) -> Result<(), InstallerError> {
-        let user_reg = prefix.join("user.reg");
-        if !user_reg.exists() {
-            return Err(InstallerError::Unknown(format!("Wine registry file not found: {:?}", user_reg)));
+    fn remove_installed_files<'a>(
+        &self,
+        game_dir: &Path,
+        files: impl IntoIterator<Item = &'a ManifestFile>,
+    ) -> Result<(), InstallerError> {
+        for file in files {
+            let path = game_dir.join(&file.path);
+            if path.is_file() {
+                fs::remove_file(&path)?;
+            }
+
+            // Clean up directories the archive created, as long as nothing else lives there
+            if let Some(parent) = path.parent()
+                && parent != game_dir
+            {
+                let _ = fs::remove_dir(parent);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_loader_files(&self, game_dir: &Path) -> Result<(), InstallerError> {
+        for name in GEODE_LOADER_FILES {
+            let path = game_dir.join(name);
+            if path.is_file() {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the xinput1_4 override to `user.reg`, returning the edit if one was made
+    fn patch_wine_registry(&self, prefix: &Path) -> Result<Option<RegistryEdit>, InstallerError> {
+        let scope = self.options.override_scope.unwrap_or_else(|| default_override_scope(prefix));
+        let edit = self.ensure_dll_override(prefix, scope)?;
+        if edit.is_some() {
+            self.registry.flush(prefix)?;
         }
+        Ok(edit)
+    }
 
-        let mut content = fs::read_to_string(&user_reg)?;
-        self.ensure_dll_override(&mut content);
-        fs::write(&user_reg, content)?;
+    fn unpatch_wine_registry(&self, prefix: &Path, edits: &[RegistryEdit]) -> Result<(), InstallerError> {
+        let mut changed = false;
+        for edit in edits {
+            changed |= self.remove_registry_value(prefix, edit)?;
+        }
+        if changed {
+            self.registry.flush(prefix)?;
+        }
         Ok(())
     }
 
-    fn ensure_dll_override(&self, content: &mut String) {
-        const SECTION: &str = "[Software\\\\Wine\\\\DllOverrides]";
-        const ENTRY: &str = "\"xinput1_4\"=\"native,builtin\"";
+    /// Make sure nothing runs in the prefix: the wineserver writes its in-memory registry back
+    /// to `user.reg` when it exits, which would silently undo our edits
+    fn ensure_prefix_idle(&self, prefix: &Path) -> Result<(), InstallerError> {
+        self.wait_for_exit(
+            |scanner| scanner.using_prefix(prefix),
+            |running| InstallerError::PrefixInUse(format!(
+                "{} running in {:?}. Close Geometry Dash and wait for Wine to shut down \
+                 (or run `wineserver -k` with WINEPREFIX set), then try again.",
+                running, prefix
+            )),
+        )
+    }
+
+    /// Make sure Steam is closed: it saves `localconfig.vdf` from memory when it exits,
+    /// which would silently undo our launch options
+    fn ensure_steam_closed(&self) -> Result<(), InstallerError> {
+        self.wait_for_exit(
+            |scanner| scanner.steam_clients(),
+            |running| InstallerError::SteamRunning(format!(
+                "{}. Quit Steam (Steam > Exit) so it doesn't overwrite the launch options, then try again.",
+                running
+            )),
+        )
+    }
+
+    /// Fail with `error` while `find` reports running processes, or wait for them to exit
+    /// when the `wait` option is set
+    fn wait_for_exit(
+        &self,
+        find: impl Fn(&WineProcesses) -> Result<Vec<PrefixProcess>, InstallerError>,
+        error: impl Fn(String) -> InstallerError,
+    ) -> Result<(), InstallerError> {
+        let scanner = self.options.proc_root.clone().map_or_else(WineProcesses::new, WineProcesses::at);
+        let mut waiting = false;
+
+        loop {
+            let processes = find(&scanner)?;
+            if processes.is_empty() {
+                return Ok(());
+            }
+
+            let running = processes.iter()
+                .map(|process| format!("{} (pid {})", process.name, process.pid))
+                .collect::<Vec<_>>()
+                .join(", ");
 
-        if content.contains("\"xinput1_4\"=") {
-            return; // Already configured
+            if !self.options.wait {
+                return Err(error(running));
+            }
+
+            if !waiting {
+                println!("Waiting for {} to exit...", running);
+                waiting = true;
+            }
+            thread::sleep(PREFIX_POLL_INTERVAL);
         }
+    }
 
-        if !content.contains(SECTION) {
-            self.add_dll_overrides_section(content);
-        } else {
-            self.add_dll_entry_to_section(content, SECTION, ENTRY);
+    /// Back up a registry file before the backend changes it
+    fn backup_registry(&self, prefix: &Path, file: &str) -> Result<(), InstallerError> {
+        if !prefix.join(file).exists() {
+            return Ok(());
+        }
+
+        let mut backups = RegistryBackups::in_prefix(prefix);
+        if let Some(keep) = self.options.registry_backups {
+            backups = backups.with_keep(keep);
+        }
+
+        let backup = backups.create(file)?;
+        if backup.path.exists() {
+            println!("Backed up {} to {:?}", file, backup.path);
         }
+        Ok(())
     }
 
-    fn add_dll_overrides_section(&self, content: &mut String) {
-        let timestamp = current_timestamp();
-        let hex_time = current_hex_timestamp();
-        content.push_str(&format!(
-            "\n\n[Software\\\\Wine\\\\DllOverrides] {}\n#time={}\n\"xinput1_4\"=\"native,builtin\"\n",
-            timestamp, hex_time
-        ));
+    fn ensure_dll_override(&self, prefix: &Path, scope: OverrideScope) -> Result<Option<RegistryEdit>, InstallerError> {
+        let Some(existing) = read_dll_override(self.registry.as_ref(), prefix)? else {
+            let created_section = self.registry.read_key(prefix, scope.section())?.is_none();
+            self.backup_registry(prefix, USER_REG)?;
+            self.registry.set_value(prefix, scope.section(), XINPUT_DLL, XINPUT_OVERRIDE)?;
+            return Ok(Some(dll_override_edit(scope, created_section)));
+        };
+
+        if existing.loads_native() {
+            println!("xinput1_4 override already set ({} scope)", existing.scope);
+            return Ok(None);
+        }
+
+        // Wine would keep using its own xinput1_4.dll and Geode would never load
+        println!(
+            "⚠️ xinput1_4 is overridden to \"{}\" ({} scope), which keeps Geode from loading; changing it to \"{}\"",
+            existing.value, existing.scope, XINPUT_OVERRIDE
+        );
+        self.backup_registry(prefix, USER_REG)?;
+        self.registry.set_value(prefix, existing.scope.section(), XINPUT_DLL, XINPUT_OVERRIDE)?;
+
+        Ok(Some(RegistryEdit {
+            previous: Some(existing.value),
+            ..dll_override_edit(existing.scope, false)
+        }))
+    }
+
+    /// Add the xinput1_4 override to Geometry Dash's launch options in every Steam account,
+    /// keeping whatever options the user already has
+    fn set_launch_options(&self, transaction: &mut InstallTransaction) -> Result<Vec<LaunchOptionsEdit>, InstallerError> {
+        let configs = self.finder.user_config_files();
+        if configs.is_empty() {
+            return Err(InstallerError::Installation(
+                "No Steam account configuration (userdata/<id>/config/localconfig.vdf) found; \
+                 log in to Steam once, then try again".into(),
+            ));
+        }
+
+        let mut edits = Vec::new();
+        for path in configs {
+            let mut config = LocalConfig::load(&path)?;
+            let current = config.launch_options(GD_APP_ID).unwrap_or_default().to_string();
+            let existing = launch_options::dll_override(&current, XINPUT_DLL);
+
+            if existing.as_deref().is_some_and(loads_native) {
+                println!("xinput1_4 override already in the launch options of {:?}", path);
+                continue;
+            }
+
+            let updated = launch_options::with_dll_override(&current, XINPUT_DLL, XINPUT_LAUNCH_OVERRIDE);
+            transaction.snapshot(&path)?;
+            config.set_launch_options(GD_APP_ID, &updated);
+            config.save()?;
+            println!("Launch options for {:?}: {}", path, updated);
+
+            edits.push(LaunchOptionsEdit {
+                config: path,
+                dll: XINPUT_DLL.to_string(),
+                mode: XINPUT_LAUNCH_OVERRIDE.to_string(),
+                previous: existing,
+            });
+        }
+        Ok(edits)
     }
 
-    fn add_dll_entry_to_section(&self, content: &mut String, section: &str, entry: &str) {
-        if let Some(section_pos) = content.find(section) {
-            let search_start = section_pos + section.len();
-            let insert_pos = content[search_start..]
-                .find("\n[")
-                .map(|pos| search_start + pos)
-                .unwrap_or(content.len());
+    /// Take recorded overrides back out of the launch options, unless the user changed them since
+    fn unset_launch_options(&self, edits: &[LaunchOptionsEdit]) -> Result<(), InstallerError> {
+        for edit in edits {
+            if !edit.config.is_file() {
+                continue;
+            }
 
-            let entry_with_newline = if insert_pos == content.len() {
-                format!("\n{}\n", entry)
-            } else {
-                format!("{}\n", entry)
+            let mut config = LocalConfig::load(&edit.config)?;
+            let current = config.launch_options(GD_APP_ID).unwrap_or_default().to_string();
+            if launch_options::dll_override(&current, &edit.dll).as_deref() != Some(edit.mode.as_str()) {
+                continue;
+            }
+
+            let updated = match &edit.previous {
+                Some(previous) => launch_options::with_dll_override(&current, &edit.dll, previous),
+                None => launch_options::without_dll_override(&current, &edit.dll),
             };
-            content.insert_str(insert_pos, &entry_with_newline);
+            config.set_launch_options(GD_APP_ID, &updated);
+            config.save()?;
         }
+        Ok(())
+    }
+
+    /// The xinput1_4 mode in Geometry Dash's launch options of the first Steam account that has one
+    fn launch_option_override(&self) -> Result<Option<String>, InstallerError> {
+        for path in self.finder.user_config_files() {
+            let config = LocalConfig::load(&path)?;
+            if let Some(mode) = config.launch_options(GD_APP_ID)
+                .and_then(|options| launch_options::dll_override(options, XINPUT_DLL))
+            {
+                return Ok(Some(mode));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Drop a value the installer added, along with its section if the installer created it
+    /// and nothing else is left in it. Returns whether the registry was changed.
+    fn remove_registry_value(&self, prefix: &Path, edit: &RegistryEdit) -> Result<bool, InstallerError> {
+        let section = edit_section(edit);
+        let Some(values) = self.registry.read_key(prefix, &section)? else {
+            return Ok(false);
+        };
+        let Some(current) = values.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&edit.name))
+            .map(|(_, value)| value)
+        else {
+            return Ok(false);
+        };
+
+        if let Some(previous) = &edit.previous {
+            // Put back the value the installer replaced, unless someone changed it since
+            if *current != edit.value {
+                return Ok(false);
+            }
+            self.backup_registry(prefix, &edit.file)?;
+            self.registry.set_value(prefix, &section, &edit.name, previous)?;
+            return Ok(true);
+        }
+
+        self.backup_registry(prefix, &edit.file)?;
+        if edit.created_section && values.len() == 1 {
+            self.registry.delete_key(prefix, &section)?;
+        } else {
+            self.registry.delete_value(prefix, &section, &edit.name)?;
+        }
+        Ok(true)
     }
 }
 
@@ -288,13 +1294,104 @@ impl Default for GeodeInstaller {
     }
 }
 
+fn archive_name(tag: &str) -> String {
+    format!("geode-{}-win.zip", tag)
+}
+
+/// Find `sha256:<hex>` in the `digest` field of the named asset of a GitHub release
+pub fn parse_asset_digest(release: &Value, asset_name: &str) -> Option<ArchiveDigest> {
+    let asset = release["assets"].as_array()?
+        .iter()
+        .find(|asset| asset["name"].as_str() == Some(asset_name))?;
+
+    let sha256 = asset["digest"].as_str()?.strip_prefix("sha256:")?;
+    Some(ArchiveDigest {
+        sha256: sha256.to_string(),
+        size: asset["size"].as_u64(),
+    })
+}
+
+/// The releases in the `payload` of a Geode API version listing
+pub fn parse_releases(payload: &Value, include_prereleases: bool) -> Result<Vec<LoaderRelease>, InstallerError> {
+    // Paginated responses wrap the list in `data`
+    let entries = payload["data"].as_array()
+        .or_else(|| payload.as_array())
+        .ok_or_else(|| InstallerError::Unknown("Failed to extract version list from API response".into()))?;
+
+    Ok(entries.iter()
+        .filter_map(|entry| {
+            Some(LoaderRelease {
+                tag: entry["tag"].as_str()?.to_string(),
+                prerelease: entry["prerelease"].as_bool().unwrap_or(false),
+                created_at: entry["created_at"].as_str().map(String::from),
+                gd_version: entry["gd"]["win"].as_str().map(String::from),
+            })
+        })
+        .filter(|release| include_prereleases || !release.prerelease)
+        .collect())
+}
+
+/// Accept both `4.2.0` and `v4.2.0`
+pub fn normalize_tag(version: &str) -> String {
+    let version = version.trim();
+    if version.starts_with('v') {
+        version.to_string()
+    } else {
+        format!("v{}", version)
+    }
+}
+
+fn dll_override_edit(scope: OverrideScope, created_section: bool) -> RegistryEdit {
+    RegistryEdit {
+        file: USER_REG.to_string(),
+        section: scope.section().to_string(),
+        name: XINPUT_DLL.to_string(),
+        value: XINPUT_OVERRIDE.to_string(),
+        created_section,
+        previous: None,
+    }
+}
+
+/// Section name of a recorded edit as a plain key path
+fn edit_section(edit: &RegistryEdit) -> String {
+    // Manifests from before the registry parser stored the escaped form from the file
+    if edit.section.contains("\\\\") {
+        wine_registry::unescape(&edit.section)
+    } else {
+        edit.section.clone()
+    }
+}
+
+/// The override Wine uses for Geometry Dash: a per-application one wins over the prefix-wide one
+fn read_dll_override(backend: &dyn RegistryBackend, prefix: &Path) -> Result<Option<DllOverride>, InstallerError> {
+    for scope in [OverrideScope::App, OverrideScope::Prefix] {
+        if let Some(value) = backend.read_value(prefix, scope.section(), XINPUT_DLL)? {
+            return Ok(Some(DllOverride { scope, value }));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether a DLL override mode such as `native,builtin` or `n,b` tries the native DLL first
+fn loads_native(mode: &str) -> bool {
+    let first = mode.split(',').next().unwrap_or("").trim();
+    first.eq_ignore_ascii_case("native") || first.eq_ignore_ascii_case("n")
+}
+
+/// Proton gives every Steam game its own prefix (`compatdata/<app id>/pfx`), so a prefix-wide
+/// override only affects Geometry Dash there; anywhere else it could break other programs
+fn default_override_scope(prefix: &Path) -> OverrideScope {
+    let is_proton_prefix = prefix.ends_with(Path::new("compatdata").join(GD_APP_ID).join("pfx"));
+    if is_proton_prefix {
+        OverrideScope::Prefix
+    } else {
+        OverrideScope::App
+    }
+}
+
 fn current_timestamp() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs()
 }
-
-fn current_hex_timestamp() -> String {
-    format!("{:x}", current_timestamp())
-}
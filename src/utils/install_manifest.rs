@@ -0,0 +1,165 @@
+use crate::errors::InstallerError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// File name of the manifest, stored in the game directory
+pub const MANIFEST_FILE_NAME: &str = ".geode-installer-manifest.json";
+
+/// Everything an installation wrote, so it can be verified, updated or removed later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallManifest {
+    /// Geode loader tag, e.g. `v4.2.0`
+    pub tag: String,
+    /// Unix timestamp of the installation
+    pub installed_at: u64,
+    pub game_dir: PathBuf,
+    pub prefix: PathBuf,
+    pub files: Vec<ManifestFile>,
+    pub registry: Vec<RegistryEdit>,
+    /// Steam launch options changed instead of the registry
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub launch_options: Vec<LaunchOptionsEdit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Path relative to the game directory
+    pub path: PathBuf,
+    pub size: u64,
+    pub crc32: u32,
+    pub sha256: String,
+}
+
+/// A value the installer added to or changed in a Wine registry file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryEdit {
+    /// Registry file relative to the prefix, e.g. `user.reg`
+    pub file: String,
+    /// Key path, e.g. `Software\Wine\DllOverrides`
+    pub section: String,
+    pub name: String,
+    pub value: String,
+    /// Whether the section did not exist before the installer added the value
+    pub created_section: bool,
+    /// The value that was replaced, restored on uninstall; `None` if the value was added
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+}
+
+/// A DLL override the installer put into Geometry Dash's Steam launch options
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchOptionsEdit {
+    /// The account's `localconfig.vdf`
+    pub config: PathBuf,
+    pub dll: String,
+    /// Mode set in `WINEDLLOVERRIDES`, e.g. `n,b`
+    pub mode: String,
+    /// The mode that was replaced, restored on uninstall; `None` if the override was added
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileProblem {
+    Missing(PathBuf),
+    SizeMismatch { path: PathBuf, expected: u64, actual: u64 },
+    HashMismatch(PathBuf),
+}
+
+impl fmt::Display for FileProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileProblem::Missing(path) => write!(f, "{} is missing", path.display()),
+            FileProblem::SizeMismatch { path, expected, actual } => write!(
+                f,
+                "{} has size {} bytes, expected {}",
+                path.display(), actual, expected
+            ),
+            FileProblem::HashMismatch(path) => write!(f, "{} has been modified", path.display()),
+        }
+    }
+}
+
+impl InstallManifest {
+    pub fn path_in(game_dir: &Path) -> PathBuf {
+        game_dir.join(MANIFEST_FILE_NAME)
+    }
+
+    /// Load the manifest of a game directory, if Geode was installed there by this tool
+    pub fn load(game_dir: &Path) -> Result<Option<Self>, InstallerError> {
+        let path = Self::path_in(game_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(&path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    pub fn save(&self) -> Result<(), InstallerError> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(Self::path_in(&self.game_dir), content)?;
+        Ok(())
+    }
+
+    pub fn remove(game_dir: &Path) -> Result<(), InstallerError> {
+        let path = Self::path_in(game_dir);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Compare the recorded files against what is currently on disk
+    pub fn verify(&self) -> Result<Vec<FileProblem>, InstallerError> {
+        let mut problems = Vec::new();
+
+        for file in &self.files {
+            let full_path = self.game_dir.join(&file.path);
+            if !full_path.is_file() {
+                problems.push(FileProblem::Missing(file.path.clone()));
+                continue;
+            }
+
+            let actual = fs::metadata(&full_path)?.len();
+            if actual != file.size {
+                problems.push(FileProblem::SizeMismatch {
+                    path: file.path.clone(),
+                    expected: file.size,
+                    actual,
+                });
+            } else if sha256_file(&full_path)? != file.sha256 {
+                problems.push(FileProblem::HashMismatch(file.path.clone()));
+            }
+        }
+
+        Ok(problems)
+    }
+}
+
+impl ManifestFile {
+    /// Record a freshly extracted file, `crc32` coming from the archive entry
+    pub fn record(game_dir: &Path, relative: &Path, crc32: u32) -> Result<Self, InstallerError> {
+        let full_path = game_dir.join(relative);
+        Ok(Self {
+            path: relative.to_path_buf(),
+            size: fs::metadata(&full_path)?.len(),
+            crc32,
+            sha256: sha256_file(&full_path)?,
+        })
+    }
+}
+
+pub fn sha256_file(path: &Path) -> Result<String, InstallerError> {
+    sha256_reader(&mut File::open(path)?)
+}
+
+pub fn sha256_reader(reader: &mut dyn Read) -> Result<String, InstallerError> {
+    let mut hasher = Sha256::new();
+    io::copy(reader, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
@@ -0,0 +1,214 @@
+use crate::errors::InstallerError;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Work directory inside the game directory, so every move stays on one filesystem
+const WORK_DIR_NAME: &str = ".geode-installer-transaction";
+/// Files placed in the game directory, one relative path per line, for recovering from a killed run
+const JOURNAL_NAME: &str = "placed";
+/// Written once a transaction is committed, so a leftover work directory isn't rolled back
+const COMMITTED_MARKER: &str = "committed";
+
+/// Changes to a game directory and Wine registry files that are only kept if the
+/// whole installation succeeds.
+///
+/// New files are extracted into a staging directory first, then swapped into place with
+/// renames. Anything they replace or that gets removed is moved into a backup directory,
+/// and registry files are snapshotted before being edited, so `rollback` can put
+/// everything back the way it was. If the process is killed instead, the next `begin`
+/// restores the game directory from the backups left on disk.
+pub struct InstallTransaction {
+    game_dir: PathBuf,
+    work_dir: PathBuf,
+    /// Files moved into the game directory, relative to it
+    placed: Vec<PathBuf>,
+    /// Files moved out of the game directory into the backup, relative to it
+    backed_up: Vec<PathBuf>,
+    /// Directories created in the game directory, outermost first
+    created_dirs: Vec<PathBuf>,
+    /// Original contents of edited files, `None` if they didn't exist
+    snapshots: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl InstallTransaction {
+    pub fn begin(game_dir: &Path) -> Result<Self, InstallerError> {
+        let work_dir = game_dir.join(WORK_DIR_NAME);
+        if work_dir.exists() {
+            // Left behind by a run that was killed before it could clean up
+            Self::recover(game_dir, &work_dir)?;
+        }
+        fs::create_dir_all(work_dir.join("staging"))?;
+        fs::create_dir_all(work_dir.join("backup"))?;
+
+        Ok(Self {
+            game_dir: game_dir.to_path_buf(),
+            work_dir,
+            placed: Vec::new(),
+            backed_up: Vec::new(),
+            created_dirs: Vec::new(),
+            snapshots: Vec::new(),
+        })
+    }
+
+    /// Directory to extract new files into before they are committed
+    pub fn staging_dir(&self) -> PathBuf {
+        self.work_dir.join("staging")
+    }
+
+    /// Move a staged file into the game directory, backing up the file it replaces
+    pub fn place(&mut self, relative: &Path) -> Result<(), InstallerError> {
+        let target = self.game_dir.join(relative);
+        if let Some(parent) = target.parent() {
+            self.create_dirs(parent)?;
+        }
+
+        if target.exists() {
+            self.back_up(relative)?;
+        }
+        // Journaled first: recovery skips files that never arrived
+        let mut journal = OpenOptions::new().create(true).append(true).open(self.work_dir.join(JOURNAL_NAME))?;
+        writeln!(journal, "{}", relative.to_string_lossy())?;
+        fs::rename(self.staging_dir().join(relative), &target)?;
+        self.placed.push(relative.to_path_buf());
+        Ok(())
+    }
+
+    /// Remove a file from the game directory, keeping it in the backup until the end
+    pub fn remove(&mut self, relative: &Path) -> Result<(), InstallerError> {
+        if self.game_dir.join(relative).is_file() {
+            self.back_up(relative)?;
+        }
+        Ok(())
+    }
+
+    /// Remember the current contents of `path` so a later edit can be undone
+    pub fn snapshot(&mut self, path: &Path) -> Result<(), InstallerError> {
+        if self.snapshots.iter().any(|(snapshot, _)| snapshot == path) {
+            return Ok(());
+        }
+
+        let content = if path.exists() { Some(fs::read(path)?) } else { None };
+        self.snapshots.push((path.to_path_buf(), content));
+        Ok(())
+    }
+
+    /// Keep all changes and drop the backups
+    pub fn commit(self) -> Result<(), InstallerError> {
+        fs::write(self.work_dir.join(COMMITTED_MARKER), "")?;
+        fs::remove_dir_all(&self.work_dir)?;
+        Ok(())
+    }
+
+    /// Undo every change made through this transaction
+    pub fn rollback(self) -> Result<(), InstallerError> {
+        for (path, content) in self.snapshots.iter().rev() {
+            match content {
+                Some(content) => fs::write(path, content)?,
+                None if path.exists() => fs::remove_file(path)?,
+                None => {}
+            }
+        }
+
+        for relative in self.placed.iter().rev() {
+            let path = self.game_dir.join(relative);
+            if path.is_file() {
+                fs::remove_file(path)?;
+            }
+        }
+
+        let backup_dir = self.work_dir.join("backup");
+        for relative in self.backed_up.iter().rev() {
+            fs::rename(backup_dir.join(relative), self.game_dir.join(relative))?;
+        }
+
+        for dir in self.created_dirs.iter().rev() {
+            let _ = fs::remove_dir(dir);
+        }
+
+        fs::remove_dir_all(&self.work_dir)?;
+        Ok(())
+    }
+
+    /// Undo the file changes of a run that was killed mid-transaction: remove what it placed
+    /// and move what it replaced or removed back from the backup
+    fn recover(game_dir: &Path, work_dir: &Path) -> Result<(), InstallerError> {
+        if !work_dir.join(COMMITTED_MARKER).exists() {
+            println!("Undoing an installation that was interrupted...");
+
+            let journal = fs::read_to_string(work_dir.join(JOURNAL_NAME)).unwrap_or_default();
+            for relative in journal.lines().rev() {
+                let path = game_dir.join(relative);
+                if path.is_file() {
+                    fs::remove_file(&path)?;
+                }
+                // Directories it created, if nothing else is in them
+                for dir in path.ancestors().skip(1).take_while(|dir| *dir != game_dir) {
+                    if fs::remove_dir(dir).is_err() {
+                        break;
+                    }
+                }
+            }
+
+            let backup_dir = work_dir.join("backup");
+            for relative in files_under(&backup_dir, Path::new(""))? {
+                let target = game_dir.join(&relative);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(backup_dir.join(&relative), target)?;
+            }
+        }
+
+        fs::remove_dir_all(work_dir)?;
+        Ok(())
+    }
+
+    fn back_up(&mut self, relative: &Path) -> Result<(), InstallerError> {
+        let backup = self.work_dir.join("backup").join(relative);
+        if let Some(parent) = backup.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::rename(self.game_dir.join(relative), backup)?;
+        self.backed_up.push(relative.to_path_buf());
+        Ok(())
+    }
+
+    fn create_dirs(&mut self, dir: &Path) -> Result<(), InstallerError> {
+        let mut missing = Vec::new();
+        let mut current = dir;
+        while !current.exists() {
+            missing.push(current.to_path_buf());
+            match current.parent() {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+
+        for dir in missing.into_iter().rev() {
+            fs::create_dir(&dir)?;
+            self.created_dirs.push(dir);
+        }
+        Ok(())
+    }
+}
+
+/// Files below `dir`, relative to it, with `prefix` prepended
+fn files_under(dir: &Path, prefix: &Path) -> Result<Vec<PathBuf>, InstallerError> {
+    let mut files = Vec::new();
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let relative = prefix.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            files.extend(files_under(&entry.path(), &relative)?);
+        } else {
+            files.push(relative);
+        }
+    }
+    Ok(files)
+}
@@ -0,0 +1,173 @@
+use crate::errors::InstallerError;
+use crate::utils::vdf::Vdf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+const DLL_OVERRIDES_VAR: &str = "WINEDLLOVERRIDES";
+const COMMAND_PLACEHOLDER: &str = "%command%";
+
+/// A Steam account's `userdata/<account id>/config/localconfig.vdf`, which holds the
+/// launch options set in a game's properties
+pub struct LocalConfig {
+    path: PathBuf,
+    vdf: Vdf,
+}
+
+impl LocalConfig {
+    pub fn load(path: &Path) -> Result<Self, InstallerError> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            vdf: Vdf::load(path)?,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn launch_options(&self, app_id: &str) -> Option<&str> {
+        self.vdf.local_config_apps()?.block(app_id)?.string("LaunchOptions")
+    }
+
+    /// Replace the launch options of `app_id`; empty options remove the entry
+    pub fn set_launch_options(&mut self, app_id: &str, options: &str) {
+        let app = self.vdf.local_config_apps_mut().block_or_insert(app_id);
+
+        if options.is_empty() {
+            app.remove("LaunchOptions");
+        } else {
+            app.set_string("LaunchOptions", options);
+        }
+    }
+
+    pub fn save(&self) -> Result<(), InstallerError> {
+        self.vdf.save(&self.path)
+    }
+}
+
+/// The mode `WINEDLLOVERRIDES` in `options` gives `dll`, e.g. `n,b`
+pub fn dll_override(options: &str, dll: &str) -> Option<String> {
+    let (_, value) = find_dll_overrides(options)?;
+    value.split(';')
+        .filter_map(|entry| entry.split_once('='))
+        .find(|(dlls, _)| dlls.split(',').any(|name| name.trim().eq_ignore_ascii_case(dll)))
+        .map(|(_, mode)| mode.to_string())
+}
+
+/// Launch options that override `dll` to `mode`, keeping everything else the user set.
+///
+/// An existing `WINEDLLOVERRIDES` assignment is extended, otherwise one is put in front.
+/// Options without `%command%` are arguments for the game, so `%command%` is added before them.
+pub fn with_dll_override(options: &str, dll: &str, mode: &str) -> String {
+    let entry = format!("{}={}", dll, mode);
+    let options = options.trim();
+
+    match find_dll_overrides(options) {
+        Some((span, value)) => {
+            let mut entries = without_dll(&value, dll);
+            entries.push(entry);
+            replace(options, span, &format!("{}=\"{}\"", DLL_OVERRIDES_VAR, entries.join(";")))
+        }
+        None if options.is_empty() => format!("{}=\"{}\" {}", DLL_OVERRIDES_VAR, entry, COMMAND_PLACEHOLDER),
+        None if has_command(options) => format!("{}=\"{}\" {}", DLL_OVERRIDES_VAR, entry, options),
+        None => format!("{}=\"{}\" {} {}", DLL_OVERRIDES_VAR, entry, COMMAND_PLACEHOLDER, options),
+    }
+}
+
+/// Launch options without an override for `dll`, dropping `WINEDLLOVERRIDES` if nothing else
+/// is left in it. A leading `%command%` is dropped too, since Steam passes options without
+/// one to the game as arguments anyway.
+pub fn without_dll_override(options: &str, dll: &str) -> String {
+    let options = options.trim();
+    let Some((span, value)) = find_dll_overrides(options) else {
+        return options.to_string();
+    };
+
+    let entries = without_dll(&value, dll);
+    let options = if entries.is_empty() {
+        replace(options, span, "")
+    } else {
+        replace(options, span, &format!("{}=\"{}\"", DLL_OVERRIDES_VAR, entries.join(";")))
+    };
+
+    match options.strip_prefix(COMMAND_PLACEHOLDER) {
+        Some(arguments) if arguments.is_empty() || arguments.starts_with(char::is_whitespace) => {
+            arguments.trim_start().to_string()
+        }
+        _ => options,
+    }
+}
+
+/// Entries of a `WINEDLLOVERRIDES` value with `dll` taken out of them
+fn without_dll(value: &str, dll: &str) -> Vec<String> {
+    value.split(';')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| {
+            let Some((dlls, mode)) = entry.split_once('=') else {
+                return Some(entry.to_string());
+            };
+            let kept = dlls.split(',')
+                .filter(|name| !name.trim().eq_ignore_ascii_case(dll))
+                .collect::<Vec<_>>();
+            (!kept.is_empty()).then(|| format!("{}={}", kept.join(","), mode))
+        })
+        .collect()
+}
+
+/// Byte range and unquoted value of the `WINEDLLOVERRIDES=...` assignment among the
+/// environment variables in front of `%command%`
+fn find_dll_overrides(options: &str) -> Option<(Range<usize>, String)> {
+    for (span, word) in words(options) {
+        let Some((name, value)) = word.split_once('=') else {
+            // Only leading NAME=value words are environment variables
+            return None;
+        };
+        if name == DLL_OVERRIDES_VAR {
+            return has_command(options).then(|| (span, value.replace('"', "")));
+        }
+    }
+    None
+}
+
+fn has_command(options: &str) -> bool {
+    words(options).any(|(_, word)| word == COMMAND_PLACEHOLDER)
+}
+
+/// Whitespace-separated words with their byte ranges, keeping double-quoted parts together
+fn words(options: &str) -> impl Iterator<Item = (Range<usize>, &str)> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let rest = &options[pos..];
+        let start = pos + (rest.len() - rest.trim_start().len());
+        if start == options.len() {
+            return None;
+        }
+
+        let mut quoted = false;
+        let mut end = options.len();
+        for (i, c) in options[start..].char_indices() {
+            if c == '"' {
+                quoted = !quoted;
+            } else if c.is_whitespace() && !quoted {
+                end = start + i;
+                break;
+            }
+        }
+
+        pos = end;
+        Some((start..end, &options[start..end]))
+    })
+}
+
+/// Replace a word, collapsing the whitespace around it if it is removed
+fn replace(options: &str, span: Range<usize>, with: &str) -> String {
+    if !with.is_empty() {
+        return format!("{}{}{}", &options[..span.start], with, &options[span.end..]);
+    }
+    let before = options[..span.start].trim_end();
+    let after = options[span.end..].trim_start();
+    match (before.is_empty(), after.is_empty()) {
+        (false, false) => format!("{} {}", before, after),
+        _ => format!("{}{}", before, after),
+    }
+}
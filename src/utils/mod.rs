@@ -1,2 +1,13 @@
 pub mod steam_game_finder;
-pub mod geode_installer;
\ No newline at end of file
+pub mod geode_installer;
+pub mod install_manifest;
+pub mod download_cache;
+pub mod downloader;
+pub mod install_transaction;
+pub mod wine_registry;
+pub mod registry_backup;
+pub mod wine_processes;
+pub mod registry_backend;
+pub mod vdf;
+pub mod launch_options;
+pub mod compat_tools;
\ No newline at end of file
@@ -0,0 +1,265 @@
+use crate::errors::InstallerError;
+use crate::utils::wine_registry::{RegValue, RegistryFile};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::str::FromStr;
+
+/// Registry file holding `HKEY_CURRENT_USER`, relative to the prefix
+const USER_REG: &str = "user.reg";
+const HKCU: &str = "HKEY_CURRENT_USER";
+
+/// Reads and writes `HKEY_CURRENT_USER` values of a Wine prefix.
+///
+/// Keys are paths below `HKEY_CURRENT_USER` with single backslashes,
+/// e.g. `Software\Wine\DllOverrides`, and the default value is called `""`.
+pub trait RegistryBackend {
+    /// Short name for messages, e.g. `file`
+    fn name(&self) -> &'static str;
+
+    /// String data of every value in `key`, or `None` if the key doesn't exist
+    fn read_key(&self, prefix: &Path, key: &str) -> Result<Option<Vec<(String, String)>>, InstallerError>;
+
+    /// Create or replace a string value, creating the key if needed
+    fn set_value(&self, prefix: &Path, key: &str, name: &str, value: &str) -> Result<(), InstallerError>;
+
+    fn delete_value(&self, prefix: &Path, key: &str, name: &str) -> Result<(), InstallerError>;
+
+    fn delete_key(&self, prefix: &Path, key: &str) -> Result<(), InstallerError>;
+
+    /// Make sure changes have reached `user.reg` on disk
+    fn flush(&self, _prefix: &Path) -> Result<(), InstallerError> {
+        Ok(())
+    }
+
+    fn read_value(&self, prefix: &Path, key: &str, name: &str) -> Result<Option<String>, InstallerError> {
+        Ok(self.read_key(prefix, key)?.and_then(|values| {
+            values.into_iter()
+                .find(|(value_name, _)| value_name.eq_ignore_ascii_case(name))
+                .map(|(_, data)| data)
+        }))
+    }
+}
+
+/// Available backends, as chosen on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RegistryBackendKind {
+    /// Edit `user.reg` directly
+    #[default]
+    File,
+    /// Run `wine reg` against the prefix
+    Wine,
+}
+
+impl fmt::Display for RegistryBackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryBackendKind::File => write!(f, "file"),
+            RegistryBackendKind::Wine => write!(f, "wine"),
+        }
+    }
+}
+
+impl FromStr for RegistryBackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(RegistryBackendKind::File),
+            "wine" => Ok(RegistryBackendKind::Wine),
+            _ => Err(format!("expected 'file' or 'wine', got '{}'", s)),
+        }
+    }
+}
+
+/// Edits `user.reg` in place through `wine_registry`
+pub struct FileBackend;
+
+impl FileBackend {
+    fn load(&self, prefix: &Path) -> Result<RegistryFile, InstallerError> {
+        let user_reg = prefix.join(USER_REG);
+        if !user_reg.exists() {
+            return Err(InstallerError::Unknown(format!("Wine registry file not found: {:?}", user_reg)));
+        }
+        RegistryFile::load(&user_reg)
+    }
+
+    fn save(&self, prefix: &Path, registry: &RegistryFile) -> Result<(), InstallerError> {
+        registry.save(&prefix.join(USER_REG))
+    }
+}
+
+impl RegistryBackend for FileBackend {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn read_key(&self, prefix: &Path, key: &str) -> Result<Option<Vec<(String, String)>>, InstallerError> {
+        if !prefix.join(USER_REG).exists() {
+            return Ok(None);
+        }
+
+        let registry = self.load(prefix)?;
+        Ok(registry.section(key).map(|section| {
+            section.values()
+                .map(|(name, value)| (name.to_string(), value_text(value)))
+                .collect()
+        }))
+    }
+
+    fn set_value(&self, prefix: &Path, key: &str, name: &str, value: &str) -> Result<(), InstallerError> {
+        let mut registry = self.load(prefix)?;
+        registry.section_or_insert(key).set(name, RegValue::String(value.to_string()));
+        self.save(prefix, &registry)
+    }
+
+    fn delete_value(&self, prefix: &Path, key: &str, name: &str) -> Result<(), InstallerError> {
+        let mut registry = self.load(prefix)?;
+        if let Some(section) = registry.section_mut(key)
+            && section.remove(name).is_some()
+        {
+            self.save(prefix, &registry)?;
+        }
+        Ok(())
+    }
+
+    fn delete_key(&self, prefix: &Path, key: &str) -> Result<(), InstallerError> {
+        let mut registry = self.load(prefix)?;
+        if registry.remove_section(key) {
+            self.save(prefix, &registry)?;
+        }
+        Ok(())
+    }
+}
+
+/// Goes through Wine's own `reg.exe`, so Wine decides how `user.reg` is written.
+/// Works with Proton too when pointed at its bundled `files/bin/wine`.
+pub struct WineBackend {
+    wine: PathBuf,
+}
+
+impl WineBackend {
+    pub fn new(wine: PathBuf) -> Self {
+        Self { wine }
+    }
+
+    /// `wineserver` next to the `wine` binary, or from `PATH` if `wine` is
+    fn wineserver(&self) -> PathBuf {
+        match self.wine.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.join("wineserver"),
+            _ => PathBuf::from("wineserver"),
+        }
+    }
+
+    fn reg(&self, prefix: &Path, args: &[&str]) -> Result<Output, InstallerError> {
+        Command::new(&self.wine)
+            .arg("reg")
+            .args(args)
+            .env("WINEPREFIX", prefix)
+            .env("WINEDEBUG", "-all")
+            .output()
+            .map_err(|e| InstallerError::Unknown(format!("Failed to run {:?}: {}", self.wine, e)))
+    }
+
+    fn reg_checked(&self, prefix: &Path, args: &[&str]) -> Result<(), InstallerError> {
+        let output = self.reg(prefix, args)?;
+        if !output.status.success() {
+            return Err(InstallerError::Registry(format!(
+                "`wine reg {}` failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl RegistryBackend for WineBackend {
+    fn name(&self) -> &'static str {
+        "wine"
+    }
+
+    fn read_key(&self, prefix: &Path, key: &str) -> Result<Option<Vec<(String, String)>>, InstallerError> {
+        let output = self.reg(prefix, &["query", &hkcu(key)])?;
+        if !output.status.success() {
+            // reg.exe exits with 1 when the key doesn't exist
+            return Ok(None);
+        }
+        Ok(Some(parse_reg_query(&String::from_utf8_lossy(&output.stdout))))
+    }
+
+    fn set_value(&self, prefix: &Path, key: &str, name: &str, value: &str) -> Result<(), InstallerError> {
+        let key = hkcu(key);
+        if name.is_empty() {
+            self.reg_checked(prefix, &["add", &key, "/ve", "/t", "REG_SZ", "/d", value, "/f"])
+        } else {
+            self.reg_checked(prefix, &["add", &key, "/v", name, "/t", "REG_SZ", "/d", value, "/f"])
+        }
+    }
+
+    fn delete_value(&self, prefix: &Path, key: &str, name: &str) -> Result<(), InstallerError> {
+        let key = hkcu(key);
+        if name.is_empty() {
+            self.reg_checked(prefix, &["delete", &key, "/ve", "/f"])
+        } else {
+            self.reg_checked(prefix, &["delete", &key, "/v", name, "/f"])
+        }
+    }
+
+    fn delete_key(&self, prefix: &Path, key: &str) -> Result<(), InstallerError> {
+        self.reg_checked(prefix, &["delete", &hkcu(key), "/f"])
+    }
+
+    fn flush(&self, prefix: &Path) -> Result<(), InstallerError> {
+        // The wineserver only writes user.reg when it exits; wait for that
+        let status = Command::new(self.wineserver())
+            .arg("-w")
+            .env("WINEPREFIX", prefix)
+            .status()
+            .map_err(|e| InstallerError::Unknown(format!("Failed to run wineserver: {}", e)))?;
+
+        if !status.success() {
+            return Err(InstallerError::Registry(format!("`wineserver -w` exited with {}", status)));
+        }
+        Ok(())
+    }
+}
+
+pub fn backend(kind: RegistryBackendKind, wine: Option<PathBuf>) -> Box<dyn RegistryBackend> {
+    match kind {
+        RegistryBackendKind::File => Box::new(FileBackend),
+        RegistryBackendKind::Wine => Box::new(WineBackend::new(wine.unwrap_or_else(|| PathBuf::from("wine")))),
+    }
+}
+
+fn hkcu(key: &str) -> String {
+    format!("{}\\{}", HKCU, key)
+}
+
+/// Text of a value as `reg query` would show it
+fn value_text(value: &RegValue) -> String {
+    match value {
+        RegValue::String(value) | RegValue::TypedString { value, .. } => value.clone(),
+        RegValue::Dword(value) => format!("0x{:x}", value),
+        RegValue::Hex { bytes, .. } => bytes.iter().map(|byte| format!("{:02X}", byte)).collect(),
+    }
+}
+
+/// Values from `reg query` output:
+///
+/// ```text
+/// HKEY_CURRENT_USER\Software\Wine\DllOverrides
+///     xinput1_4    REG_SZ    native,builtin
+/// ```
+fn parse_reg_query(output: &str) -> Vec<(String, String)> {
+    output.lines()
+        .filter_map(|line| {
+            let line = line.strip_prefix("    ")?;
+            let (name, rest) = line.split_once("    REG_")?;
+            let data = rest.split_once("    ").map(|(_, data)| data).unwrap_or("");
+
+            let name = if name == "(Default)" { "" } else { name };
+            Some((name.to_string(), data.trim_end_matches('\r').to_string()))
+        })
+        .collect()
+}
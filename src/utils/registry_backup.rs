@@ -0,0 +1,152 @@
+use crate::errors::InstallerError;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default number of backups kept per registry file
+pub const DEFAULT_KEEP: usize = 10;
+
+const BACKUP_DIR_NAME: &str = ".geode-installer-backups";
+
+/// Copies of a prefix's registry files taken before the installer changes them,
+/// stored as `<prefix>/.geode-installer-backups/<file>.<unix time>[-<n>]`
+pub struct RegistryBackups {
+    prefix: PathBuf,
+    keep: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct RegistryBackup {
+    pub path: PathBuf,
+    /// Registry file the backup belongs to, relative to the prefix, e.g. `user.reg`
+    pub file: String,
+    /// Unix timestamp of the backup
+    pub created_at: u64,
+}
+
+impl RegistryBackup {
+    /// File name of the backup, used to pick one on the command line
+    pub fn name(&self) -> String {
+        self.path.file_name().unwrap_or_default().to_string_lossy().into_owned()
+    }
+}
+
+impl RegistryBackups {
+    pub fn in_prefix(prefix: &Path) -> Self {
+        Self {
+            prefix: prefix.to_path_buf(),
+            keep: DEFAULT_KEEP,
+        }
+    }
+
+    /// Keep `keep` backups per file; at least the one just taken is always kept
+    pub fn with_keep(mut self, keep: usize) -> Self {
+        self.keep = keep.max(1);
+        self
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        self.prefix.join(BACKUP_DIR_NAME)
+    }
+
+    /// Copy `file` (relative to the prefix) into the backup directory, then drop the
+    /// oldest backups of it beyond the configured count
+    pub fn create(&self, file: &str) -> Result<RegistryBackup, InstallerError> {
+        let dir = self.dir();
+        fs::create_dir_all(&dir)?;
+
+        let created_at = now();
+        // Several edits within the same second get increasing suffixes, so they still sort newest first
+        let same_second = self.entries()?.into_iter()
+            .filter(|(_, backup)| backup.file == file && backup.created_at == created_at)
+            .map(|(counter, _)| counter)
+            .max();
+        let path = match same_second {
+            Some(counter) => dir.join(format!("{}.{}-{}", file, created_at, counter + 1)),
+            None => dir.join(format!("{}.{}", file, created_at)),
+        };
+
+        fs::copy(self.prefix.join(file), &path)?;
+        self.prune(file)?;
+
+        Ok(RegistryBackup {
+            path,
+            file: file.to_string(),
+            created_at,
+        })
+    }
+
+    /// All backups in the prefix, newest first
+    pub fn list(&self) -> Result<Vec<RegistryBackup>, InstallerError> {
+        let mut backups = self.entries()?;
+        backups.sort_by(|(a_counter, a), (b_counter, b)| {
+            (b.created_at, b_counter).cmp(&(a.created_at, a_counter))
+        });
+        Ok(backups.into_iter().map(|(_, backup)| backup).collect())
+    }
+
+    /// Put a backup back in place, after backing up the current file so the restore can be undone
+    pub fn restore(&self, backup: &RegistryBackup) -> Result<(), InstallerError> {
+        // Read first: backing up the current file may prune the one being restored
+        let content = fs::read(&backup.path)?;
+
+        let target = self.prefix.join(&backup.file);
+        if target.exists() {
+            self.create(&backup.file)?;
+        }
+
+        let temp = self.prefix.join(format!(".{}.restore", backup.file));
+        fs::write(&temp, content)?;
+        fs::rename(&temp, &target)?;
+        Ok(())
+    }
+
+    /// Backups with the suffix that orders those taken within the same second
+    fn entries(&self) -> Result<Vec<(u32, RegistryBackup)>, InstallerError> {
+        let dir = self.dir();
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if let Some((file, created_at, counter)) = parse_backup_name(name) {
+                backups.push((counter, RegistryBackup {
+                    file: file.to_string(),
+                    created_at,
+                    path: path.clone(),
+                }));
+            }
+        }
+        Ok(backups)
+    }
+
+    fn prune(&self, file: &str) -> Result<(), InstallerError> {
+        let backups = self.list()?;
+        for backup in backups.iter().filter(|backup| backup.file == file).skip(self.keep) {
+            fs::remove_file(&backup.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// `user.reg.1700000000-2` → (`user.reg`, 1700000000, 2)
+fn parse_backup_name(name: &str) -> Option<(&str, u64, u32)> {
+    let (file, stamp) = name.rsplit_once('.')?;
+    let (created_at, counter) = match stamp.split_once('-') {
+        Some((created_at, counter)) => (created_at, counter.parse().ok()?),
+        None => (stamp, 0),
+    };
+    Some((file, created_at.parse().ok()?, counter))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
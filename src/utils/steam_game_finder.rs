@@ -1,7 +1,14 @@
+use crate::utils::compat_tools::{self, CompatTool, CompatToolInfo};
+use crate::utils::vdf::{Vdf, VdfValue};
 use homedir::my_home;
-use std::collections::{HashMap, HashSet};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Directory Flatpak keeps Steam's data in, relative to the home directory
+const FLATPAK_DATA: &str = ".var/app/com.valvesoftware.Steam";
+const SNAP_DATA: &str = "snap/steam";
 
 #[derive(Debug, Clone)]
 #[allow(unused)]
@@ -10,273 +17,503 @@ pub struct GameInfo {
     pub game_path: PathBuf,
     pub proton_prefix: Option<PathBuf>,
     pub library_path: PathBuf,
+    pub state_flags: StateFlags,
+    pub build_id: Option<u64>,
+    /// Size of the pending or last update, and how much of it has been downloaded
+    pub bytes_to_download: u64,
+    pub bytes_downloaded: u64,
+    /// The Proton version or other compatibility tool Steam runs the game with
+    pub compat: CompatToolInfo,
 }
 
-pub struct SteamGameFinder {
-    steam_root: Option<PathBuf>,
-    library_folders: Vec<PathBuf>,
+/// Steam's `StateFlags` for an app, a bitmask of what Steam is doing with it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StateFlags(pub u32);
+
+impl StateFlags {
+    pub const UNINSTALLED: u32 = 1;
+    pub const UPDATE_REQUIRED: u32 = 2;
+    pub const FULLY_INSTALLED: u32 = 4;
+    pub const APP_RUNNING: u32 = 64;
+
+    const NAMES: [(u32, &'static str); 21] = [
+        (Self::UNINSTALLED, "uninstalled"),
+        (Self::UPDATE_REQUIRED, "update required"),
+        (Self::FULLY_INSTALLED, "fully installed"),
+        (8, "encrypted"),
+        (16, "locked"),
+        (32, "files missing"),
+        (Self::APP_RUNNING, "running"),
+        (128, "files corrupt"),
+        (256, "updating"),
+        (512, "update paused"),
+        (1024, "update started"),
+        (2048, "uninstalling"),
+        (4096, "backing up"),
+        (65536, "reconfiguring"),
+        (131072, "validating"),
+        (262144, "adding files"),
+        (524288, "preallocating"),
+        (1048576, "downloading"),
+        (2097152, "staging"),
+        (4194304, "committing"),
+        (8388608, "stopping update"),
+    ];
+
+    pub fn contains(self, flag: u32) -> bool {
+        self.0 & flag == flag
+    }
+
+    /// Installed with nothing pending; the game may be running
+    pub fn is_fully_installed(self) -> bool {
+        self.contains(Self::FULLY_INSTALLED) && self.0 & !(Self::FULLY_INSTALLED | Self::APP_RUNNING) == 0
+    }
 }
 
-impl SteamGameFinder {
-    pub fn new() -> Self {
-        let steam_root = Self::find_steam_root();
-        let library_folders = Self::discover_library_folders(&steam_root);
-        
-        Self {
-            steam_root,
-            library_folders,
+impl fmt::Display for StateFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = Self::NAMES.iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name)
+            .collect();
+
+        match names.is_empty() {
+            true => write!(f, "unknown state {}", self.0),
+            false => f.write_str(&names.join(", ")),
         }
     }
+}
 
-    pub fn steam_root(&self) -> Option<&PathBuf> {
-        self.steam_root.as_ref()
-    }
+/// An app Steam has installed, as recorded in its `appmanifest_<app id>.acf`
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstalledApp {
+    pub app_id: String,
+    pub name: String,
+    pub build_id: Option<u64>,
+    /// The app's directory under `steamapps/common`
+    pub install_dir: PathBuf,
+    pub state_flags: StateFlags,
+    pub size_on_disk: u64,
+    /// Unix time of the last update
+    pub last_updated: Option<u64>,
+    pub bytes_to_download: u64,
+    pub bytes_downloaded: u64,
+    /// The `steamapps` directory of the library the app is installed in
+    pub library_path: PathBuf,
+    /// The app's `compatdata/<app id>/pfx`, if it has been run with Proton
+    pub proton_prefix: Option<PathBuf>,
+}
 
+/// A Steam library as recorded in `steamapps/libraryfolders.vdf`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SteamLibrary {
+    /// The library directory, which contains `steamapps`
+    pub path: PathBuf,
+    pub label: String,
+    pub content_id: Option<String>,
+    /// Capacity of the drive in bytes, `None` when Steam did not record it
+    pub total_size: Option<u64>,
+    /// Installed app ids and their size in bytes, `None` when Steam did not list them
+    pub apps: Option<BTreeMap<String, u64>>,
+}
 
-    #[allow(unused)]
-    pub fn library_folders(&self) -> &[PathBuf] {
-        &self.library_folders
+impl SteamLibrary {
+    /// A library Steam has told us nothing else about
+    fn unlisted(path: PathBuf) -> Self {
+        Self {
+            path,
+            label: String::new(),
+            content_id: None,
+            total_size: None,
+            apps: None,
+        }
     }
 
-    pub fn get_game_info(&self, app_id: &str) -> Option<GameInfo> {
-        let (game_path, library_path) = self.find_game_by_appid(app_id)?;
-        let proton_prefix = self.find_proton_prefix(app_id, Some(&library_path));
+    /// Libraries of a `libraryfolders.vdf`, in either the current or the legacy format
+    pub fn from_vdf(data: &Vdf) -> Vec<Self> {
+        let Some(folders) = data.block("libraryfolders") else {
+            return Vec::new();
+        };
 
-        Some(GameInfo {
-            app_id: app_id.to_string(),
-            game_path,
-            library_path,
-            proton_prefix,
-        })
+        folders.iter()
+            .filter(|(key, _)| key.parse::<u32>().is_ok())
+            .filter_map(|(_, value)| match value {
+                VdfValue::Block(library) => Some(Self {
+                    path: PathBuf::from(library.string("path")?),
+                    label: library.string("label").unwrap_or_default().to_string(),
+                    content_id: library.string("contentid").map(str::to_string),
+                    total_size: library.string("totalsize").and_then(|size| size.parse().ok()),
+                    apps: library.block("apps").map(|apps| {
+                        apps.iter()
+                            .filter_map(|(app_id, size)| Some((app_id.to_string(), size.as_str()?.parse().unwrap_or(0))))
+                            .collect()
+                    }),
+                }),
+                VdfValue::String(path) => Some(Self::unlisted(PathBuf::from(path))),
+            })
+            .collect()
     }
 
-    fn find_steam_root() -> Option<PathBuf> {
-        let home = my_home().ok()??;
-
-        let candidates = [
-            home.join(".steam/steam"),
-            home.join(".steam/root"),
-            home.join(".local/share/Steam"),
-            home.join(".var/app/com.valvesoftware.Steam"),
-            home.join(".var/app/com.valvesoftware.Steam/data/Steam"),
-            PathBuf::from("/usr/share/steam"),
-        ];
+    pub fn steamapps(&self) -> PathBuf {
+        self.path.join("steamapps")
+    }
 
-        candidates.into_iter()
-            .find(|path| path.exists() && path.join("steamapps").exists())
+    /// Whether Steam recorded `app_id` as installed here
+    pub fn lists(&self, app_id: &str) -> bool {
+        self.apps.as_ref().is_some_and(|apps| apps.contains_key(app_id))
     }
+}
 
-    fn discover_library_folders(steam_root: &Option<PathBuf>) -> Vec<PathBuf> {
-        let steam_root = match steam_root {
-            Some(root) => root,
-            None => return Vec::new(),
+/// How a Steam installation was packaged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SteamPackaging {
+    Native,
+    Flatpak,
+    Snap,
+}
+
+impl SteamPackaging {
+    fn of(root: &Path) -> Self {
+        let home = my_home().ok().flatten();
+        let under = |dir: &str| match &home {
+            Some(home) => root.starts_with(home.join(dir)),
+            None => root.to_string_lossy().contains(dir),
         };
 
-        let mut folders = vec![steam_root.join("steamapps")];
-        folders.extend(Self::parse_library_folders_vdf(steam_root));
-        Self::deduplicate_paths(folders)
+        if under(FLATPAK_DATA) {
+            SteamPackaging::Flatpak
+        } else if under(SNAP_DATA) {
+            SteamPackaging::Snap
+        } else {
+            SteamPackaging::Native
+        }
     }
+}
 
-    fn parse_library_folders_vdf(steam_root: &PathBuf) -> Vec<PathBuf> {
-        let library_file = steam_root.join("steamapps/libraryfolders.vdf");
-        if !library_file.exists() {
-            return Vec::new();
-        }
+impl fmt::Display for SteamPackaging {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SteamPackaging::Native => "native",
+            SteamPackaging::Flatpak => "flatpak",
+            SteamPackaging::Snap => "snap",
+        })
+    }
+}
 
-        let data = VdfParser::parse_file(&library_file);
-        
-        data.iter()
-            .filter(|(key, _)| key.contains(".path"))
-            .filter_map(|(_, value)| {
-                let path = PathBuf::from(value).join("steamapps");
-                path.exists().then_some(path)
-            })
-            .collect()
+/// A Steam root directory, the one containing `steamapps`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SteamInstallation {
+    /// With symlinks resolved
+    pub root: PathBuf,
+    pub packaging: SteamPackaging,
+}
+
+impl SteamInstallation {
+    pub fn new(root: PathBuf) -> Self {
+        let root = fs::canonicalize(&root).unwrap_or(root);
+        let packaging = SteamPackaging::of(&root);
+        Self { root, packaging }
     }
 
-    fn deduplicate_paths(paths: Vec<PathBuf>) -> Vec<PathBuf> {
-        let mut seen = HashSet::new();
-        paths.into_iter()
-            .filter(|path| seen.insert(path.to_string_lossy().to_string()))
-            .collect()
+    /// Every Steam installation on this machine, most likely first
+    pub fn discover() -> Vec<Self> {
+        Self::discover_from(Self::candidates())
     }
 
-    fn find_game_by_appid(&self, app_id: &str) -> Option<(PathBuf, PathBuf)> {
-        for library_path in &self.library_folders {
-            if let Some(game_info) = self.check_library_for_game(library_path, app_id) {
-                return Some(game_info);
+    /// The candidates that are Steam installations, each only once however it was reached
+    pub fn discover_from(candidates: impl IntoIterator<Item = PathBuf>) -> Vec<Self> {
+        let mut installations: Vec<Self> = Vec::new();
+        for candidate in candidates {
+            if !candidate.join("steamapps").is_dir() {
+                continue;
+            }
+            let installation = Self::new(candidate);
+            if !installations.iter().any(|known| known.root == installation.root) {
+                installations.push(installation);
             }
         }
-        None
+        installations
     }
 
-    fn check_library_for_game(&self, library_path: &PathBuf, app_id: &str) -> Option<(PathBuf, PathBuf)> {
-        let acf_file = library_path.join(format!("appmanifest_{}.acf", app_id));
-        
-        if !acf_file.exists() {
-            return None;
+    /// Where Steam may be installed. Steam sets `STEAM_COMPAT_CLIENT_INSTALL_PATH` for the
+    /// programs it launches; `~/.steam/steam` and `~/.steam/root` are symlinks to the real root.
+    fn candidates() -> Vec<PathBuf> {
+        let mut candidates: Vec<PathBuf> = ["STEAM_DIR", "STEAM_COMPAT_CLIENT_INSTALL_PATH"]
+            .into_iter()
+            .filter_map(std::env::var_os)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .collect();
+
+        if let Ok(Some(home)) = my_home() {
+            candidates.extend([
+                home.join(".steam/steam"),
+                home.join(".steam/root"),
+                home.join(".local/share/Steam"),
+                home.join(FLATPAK_DATA).join(".local/share/Steam"),
+                home.join(FLATPAK_DATA).join("data/Steam"),
+                home.join(SNAP_DATA).join("common/.local/share/Steam"),
+            ]);
         }
+        candidates.extend([PathBuf::from("/usr/lib/steam"), PathBuf::from("/usr/share/steam")]);
+        candidates
+    }
+}
 
-        let acf_data = VdfParser::parse_file(&acf_file);
-        let install_dir = acf_data.get("AppState.installdir")?;
-        let game_path = library_path.join("common").join(install_dir);
-        
-        game_path.exists().then_some((game_path, library_path.clone()))
+pub struct SteamGameFinder {
+    installation: Option<SteamInstallation>,
+    libraries: Vec<SteamLibrary>,
+}
+
+impl SteamGameFinder {
+    /// Use the most likely Steam installation, see `SteamInstallation::discover`
+    pub fn new() -> Self {
+        Self::with_installation(SteamInstallation::discover().into_iter().next())
     }
 
-    fn find_proton_prefix(&self, app_id: &str, preferred_library: Option<&PathBuf>) -> Option<PathBuf> {
-        // Check preferred library first
-        if let Some(prefix) = preferred_library.and_then(|lib| Self::check_compatdata(lib, app_id)) {
-            return Some(prefix);
-        }
+    /// Use a known Steam installation instead of searching for one
+    pub fn at(steam_root: PathBuf) -> Self {
+        Self::with_installation(Some(SteamInstallation::new(steam_root)))
+    }
 
-        // Fall back to searching all libraries
-        self.library_folders.iter()
-            .find_map(|lib| Self::check_compatdata(lib, app_id))
+    fn with_installation(installation: Option<SteamInstallation>) -> Self {
+        let libraries = Self::discover_libraries(installation.as_ref().map(|installation| &installation.root));
+
+        Self {
+            installation,
+            libraries,
+        }
     }
 
-    fn check_compatdata(library_path: &PathBuf, app_id: &str) -> Option<PathBuf> {
-        let compatdata_path = library_path
-            .join("compatdata")
-            .join(app_id)
-            .join("pfx");
-        
-        compatdata_path.exists().then_some(compatdata_path)
+    pub fn installation(&self) -> Option<&SteamInstallation> {
+        self.installation.as_ref()
     }
-}
 
-impl Default for SteamGameFinder {
-    fn default() -> Self {
-        Self::new()
+    pub fn steam_root(&self) -> Option<&PathBuf> {
+        self.installation.as_ref().map(|installation| &installation.root)
     }
-}
 
-/// VDF (Valve Data Format) parser
-struct VdfParser;
+    pub fn libraries(&self) -> &[SteamLibrary] {
+        &self.libraries
+    }
 
-impl VdfParser {
-    fn parse_file(path: &PathBuf) -> HashMap<String, String> {
-        if !path.exists() {
-            return HashMap::new();
-        }
+    /// The `steamapps` directory of every library
+    pub fn library_folders(&self) -> Vec<PathBuf> {
+        self.libraries.iter().map(SteamLibrary::steamapps).collect()
+    }
 
-        let content = match fs::read_to_string(path) {
-            Ok(c) => c,
-            Err(_) => return HashMap::new(),
+    /// `localconfig.vdf` of every Steam account that has logged in on this machine
+    pub fn user_config_files(&self) -> Vec<PathBuf> {
+        let Some(entries) = self.steam_root().and_then(|root| fs::read_dir(root.join("userdata")).ok()) else {
+            return Vec::new();
         };
 
-        let mut result = HashMap::new();
-        let mut pos = 0;
-        Self::parse_recursive(&content, &mut pos, &mut result, String::new());
-        result
-    }
-
-    fn parse_recursive(
-        content: &str,
-        pos: &mut usize,
-        result: &mut HashMap<String, String>,
-        prefix: String,
-    ) {
-        let chars: Vec<char> = content.chars().collect();
-
-        while *pos < chars.len() {
-            Self::skip_whitespace(&chars, pos);
-            
-            if *pos >= chars.len() {
-                break;
-            }
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            // Directories are named after account ids; `0` holds settings from before any login
+            .filter(|entry| entry.file_name().to_str().and_then(|name| name.parse::<u64>().ok()).is_some_and(|id| id != 0))
+            .map(|entry| entry.path().join("config/localconfig.vdf"))
+            .filter(|path| path.is_file())
+            .collect();
+        files.sort();
+        files
+    }
 
-            if Self::skip_comment(&chars, pos) {
-                continue;
+    pub fn get_game_info(&self, app_id: &str) -> Option<GameInfo> {
+        let app = self.find_game_by_appid(app_id)?;
+        let compat = match self.steam_root() {
+            Some(steam_root) => {
+                let compatdata = app.proton_prefix.as_deref().and_then(Path::parent);
+                CompatToolInfo::read(steam_root, app_id, &self.compat_tools(), compatdata)
             }
+            None => CompatToolInfo::default(),
+        };
 
-            if Self::handle_closing_brace(&chars, pos) {
-                return;
-            }
+        Some(GameInfo {
+            app_id: app.app_id,
+            game_path: app.install_dir,
+            library_path: app.library_path,
+            proton_prefix: app.proton_prefix,
+            state_flags: app.state_flags,
+            build_id: app.build_id,
+            bytes_to_download: app.bytes_to_download,
+            bytes_downloaded: app.bytes_downloaded,
+            compat,
+        })
+    }
 
-            if Self::handle_opening_brace(&chars, pos) {
-                continue;
-            }
+    /// Proton versions and other compatibility tools installed in this Steam installation
+    pub fn compat_tools(&self) -> Vec<CompatTool> {
+        match self.steam_root() {
+            Some(steam_root) => compat_tools::installed_tools(steam_root, &self.library_folders()),
+            None => Vec::new(),
+        }
+    }
 
-            if chars[*pos] == '"' {
-                Self::parse_key_value(&chars, pos, result, &prefix, content);
-            } else {
-                *pos += 1;
+    /// Every app with a manifest in any library, each only once, sorted by name
+    pub fn installed_apps(&self) -> Vec<InstalledApp> {
+        let mut apps: Vec<InstalledApp> = Vec::new();
+        for library in &self.libraries {
+            let library_path = library.steamapps();
+            let Ok(entries) = fs::read_dir(&library_path) else {
+                continue;
+            };
+
+            let mut manifests: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with("appmanifest_") && name.ends_with(".acf"))
+                })
+                .collect();
+            manifests.sort();
+
+            for manifest in manifests {
+                let Some(app) = self.read_app_manifest(&manifest, &library_path) else {
+                    continue;
+                };
+                // Of two manifests for the same app, trust the library Steam lists it under
+                match apps.iter_mut().find(|known| known.app_id == app.app_id) {
+                    Some(known) if library.lists(&app.app_id) => *known = app,
+                    Some(_) => {}
+                    None => apps.push(app),
+                }
             }
         }
+        apps.sort_by_cached_key(|app| app.name.to_lowercase());
+        apps
+    }
+
+    fn discover_libraries(steam_root: Option<&PathBuf>) -> Vec<SteamLibrary> {
+        let Some(steam_root) = steam_root else {
+            return Vec::new();
+        };
+
+        let mut libraries = Self::parse_library_folders_vdf(steam_root);
+        // The Steam installation is a library too, even if the list does not mention it
+        libraries.insert(0, SteamLibrary::unlisted(steam_root.clone()));
+        Self::deduplicate_libraries(libraries)
     }
 
-    fn skip_whitespace(chars: &[char], pos: &mut usize) {
-        while *pos < chars.len() && chars[*pos].is_whitespace() {
-            *pos += 1;
+    fn parse_library_folders_vdf(steam_root: &Path) -> Vec<SteamLibrary> {
+        let library_file = steam_root.join("steamapps/libraryfolders.vdf");
+        if !library_file.exists() {
+            return Vec::new();
         }
+
+        let data = match Vdf::load(&library_file) {
+            Ok(data) => data,
+            Err(e) => {
+                println!("⚠️ Ignoring Steam library list: {}", e);
+                return Vec::new();
+            }
+        };
+
+        SteamLibrary::from_vdf(&data)
+            .into_iter()
+            .filter(|library| library.steamapps().exists())
+            .collect()
     }
 
-    fn skip_comment(chars: &[char], pos: &mut usize) -> bool {
-        if *pos + 1 < chars.len() && chars[*pos] == '/' && chars[*pos + 1] == '/' {
-            while *pos < chars.len() && chars[*pos] != '\n' {
-                *pos += 1;
+    /// Merge libraries that resolve to the same directory, keeping what the list recorded
+    /// about them in the position they first appeared
+    fn deduplicate_libraries(libraries: Vec<SteamLibrary>) -> Vec<SteamLibrary> {
+        let mut unique: Vec<(PathBuf, SteamLibrary)> = Vec::new();
+        for library in libraries {
+            let resolved = fs::canonicalize(&library.path).unwrap_or_else(|_| library.path.clone());
+            match unique.iter_mut().find(|(path, _)| *path == resolved) {
+                Some((_, existing)) if existing.apps.is_none() => {
+                    let path = existing.path.clone();
+                    *existing = SteamLibrary { path, ..library };
+                }
+                Some(_) => {}
+                None => unique.push((resolved, library)),
             }
-            return true;
         }
-        false
+        unique.into_iter().map(|(_, library)| library).collect()
     }
 
-    fn handle_closing_brace(chars: &[char], pos: &mut usize) -> bool {
-        if chars[*pos] == '}' {
-            *pos += 1;
-            return true;
+    /// Look in the libraries Steam lists the game under first, and only search the others
+    /// when that list is out of date or incomplete
+    fn find_game_by_appid(&self, app_id: &str) -> Option<InstalledApp> {
+        let (listed, others): (Vec<_>, Vec<_>) = self.libraries.iter().partition(|library| library.lists(app_id));
+
+        if let Some(game) = listed.iter().find_map(|library| self.check_library_for_game(&library.steamapps(), app_id)) {
+            return Some(game);
+        }
+        if !listed.is_empty() {
+            println!("⚠️ Steam's library list is out of date, searching every library for app {}", app_id);
         }
-        false
+
+        others.iter().find_map(|library| self.check_library_for_game(&library.steamapps(), app_id))
     }
 
-    fn handle_opening_brace(chars: &[char], pos: &mut usize) -> bool {
-        if chars[*pos] == '{' {
-            *pos += 1;
-            return true;
+    fn check_library_for_game(&self, library_path: &Path, app_id: &str) -> Option<InstalledApp> {
+        let acf_file = library_path.join(format!("appmanifest_{}.acf", app_id));
+
+        if !acf_file.exists() {
+            return None;
         }
-        false
+
+        let app = self.read_app_manifest(&acf_file, library_path)?;
+        app.install_dir.exists().then_some(app)
     }
 
-    fn parse_key_value(
-        chars: &[char],
-        pos: &mut usize,
-        result: &mut HashMap<String, String>,
-        prefix: &str,
-        content: &str,
-    ) {
-        *pos += 1; // Skip opening quote
-        
-        let key = Self::read_quoted_string(chars, pos);
-        Self::skip_whitespace(chars, pos);
-
-        if *pos < chars.len() && chars[*pos] == '"' {
-            *pos += 1;
-            let value = Self::read_quoted_string(chars, pos);
-            let full_key = Self::build_key(prefix, &key);
-            result.insert(full_key, value);
-        } else if *pos < chars.len() && chars[*pos] == '{' {
-            *pos += 1;
-            let new_prefix = Self::build_key(prefix, &key);
-            Self::parse_recursive(content, pos, result, new_prefix);
-        }
+    fn read_app_manifest(&self, acf_file: &Path, library_path: &Path) -> Option<InstalledApp> {
+        let acf_data = match Vdf::load(acf_file) {
+            Ok(data) => data,
+            Err(e) => {
+                println!("⚠️ Ignoring app manifest: {}", e);
+                return None;
+            }
+        };
+        let app_state = acf_data.app_state()?;
+        let number = |key: &str| app_state.string(key).and_then(|value| value.parse::<u64>().ok());
+
+        let app_id = app_state.string("appid")?.to_string();
+        let install_dir = library_path.join("common").join(app_state.string("installdir")?);
+        let proton_prefix = self.find_proton_prefix(&app_id, Some(library_path));
+
+        Some(InstalledApp {
+            name: app_state.string("name").unwrap_or_default().to_string(),
+            build_id: number("buildid"),
+            install_dir,
+            state_flags: StateFlags(app_state.string("StateFlags").and_then(|flags| flags.parse().ok()).unwrap_or(0)),
+            size_on_disk: number("SizeOnDisk").unwrap_or(0),
+            last_updated: number("LastUpdated"),
+            bytes_to_download: number("BytesToDownload").unwrap_or(0),
+            bytes_downloaded: number("BytesDownloaded").unwrap_or(0),
+            library_path: library_path.to_path_buf(),
+            proton_prefix,
+            app_id,
+        })
     }
 
-    fn read_quoted_string(chars: &[char], pos: &mut usize) -> String {
-        let mut s = String::new();
-        while *pos < chars.len() && chars[*pos] != '"' {
-            s.push(chars[*pos]);
-            *pos += 1;
+    fn find_proton_prefix(&self, app_id: &str, preferred_library: Option<&Path>) -> Option<PathBuf> {
+        // Check preferred library first
+        if let Some(prefix) = preferred_library.and_then(|lib| Self::check_compatdata(lib, app_id)) {
+            return Some(prefix);
         }
-        *pos += 1; // Skip closing quote
-        s
+
+        // Fall back to searching all libraries
+        self.libraries.iter()
+            .find_map(|lib| Self::check_compatdata(&lib.steamapps(), app_id))
     }
 
-    fn build_key(prefix: &str, key: &str) -> String {
-        if prefix.is_empty() {
-            key.to_string()
-        } else {
-            format!("{}.{}", prefix, key)
-        }
+    fn check_compatdata(library_path: &Path, app_id: &str) -> Option<PathBuf> {
+        let compatdata_path = library_path
+            .join("compatdata")
+            .join(app_id)
+            .join("pfx");
+        
+        compatdata_path.exists().then_some(compatdata_path)
     }
-}
\ No newline at end of file
+}
+
+impl Default for SteamGameFinder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,676 @@
+use crate::errors::InstallerError;
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Deepest nesting accepted, so a malformed file can't exhaust the stack
+const MAX_DEPTH: usize = 256;
+/// How deep `#include` and `#base` may nest
+const MAX_INCLUDE_DEPTH: usize = 16;
+/// Conditions that hold on this platform, as Steam sets them on Linux
+const PLATFORM_CONDITIONS: &[&str] = &["LINUX", "POSIX"];
+
+/// A VDF (Valve KeyValues) document or block: keys in file order, each holding either a
+/// string or a nested block.
+///
+/// Keys may repeat and are looked up ignoring ASCII case, as Steam does. Entries guarded by a
+/// `[$WIN32]`-style condition that doesn't hold on Linux are kept but skipped by lookups.
+/// Parsed documents remember their whitespace, comments and quoting, so writing one back
+/// only changes the entries that were edited.
+#[derive(Debug, Clone, Default)]
+pub struct Vdf {
+    entries: Vec<Entry>,
+    /// Whitespace and comments before the closing brace, or the end of the file
+    trailing: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub key: String,
+    pub value: VdfValue,
+    /// Condition without the brackets, e.g. `$WIN32` or `!$X360 && !$PS3`
+    condition: Option<String>,
+    format: Option<EntryFormat>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VdfValue {
+    String(String),
+    Block(Vdf),
+}
+
+/// How a parsed entry was written
+#[derive(Debug, Clone)]
+struct EntryFormat {
+    /// Whitespace and comments before the key
+    leading: String,
+    /// `None` if the key was written in the usual quoted form
+    key: Option<RawToken>,
+    /// Whitespace and comments between the key and its value or `{`
+    between: String,
+    /// `None` for blocks and values written in the usual quoted form
+    value: Option<RawToken>,
+    /// A condition following a string value, with the whitespace before it
+    after: String,
+}
+
+/// A token written unusually, e.g. unquoted, reused as long as the parsed text is unchanged
+#[derive(Debug, Clone)]
+struct RawToken {
+    text: String,
+    parsed: String,
+}
+
+/// Equality of contents, ignoring formatting
+impl PartialEq for Vdf {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl Eq for Vdf {}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key && self.value == other.value && self.condition == other.condition
+    }
+}
+
+impl Eq for Entry {}
+
+impl Entry {
+    pub fn new(key: &str, value: VdfValue) -> Self {
+        Self { key: key.to_string(), value, condition: None, format: None }
+    }
+
+    pub fn condition(&self) -> Option<&str> {
+        self.condition.as_deref()
+    }
+
+    /// Whether the entry's condition, if any, holds on this platform
+    pub fn applies(&self) -> bool {
+        self.condition.as_deref().is_none_or(evaluate_condition)
+    }
+
+    /// `#include` and `#base` lines at the top level of a file
+    pub fn is_directive(&self) -> bool {
+        self.key.eq_ignore_ascii_case("#include") || self.key.eq_ignore_ascii_case("#base")
+    }
+}
+
+impl VdfValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            VdfValue::String(value) => Some(value),
+            VdfValue::Block(_) => None,
+        }
+    }
+
+    pub fn as_block(&self) -> Option<&Vdf> {
+        match self {
+            VdfValue::Block(block) => Some(block),
+            VdfValue::String(_) => None,
+        }
+    }
+}
+
+impl Vdf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: &Path) -> Result<Self, InstallerError> {
+        Self::parse(&fs::read_to_string(path)?).map_err(|e| match e {
+            InstallerError::Vdf(message) => InstallerError::Vdf(format!("{:?}, {}", path, message)),
+            e => e,
+        })
+    }
+
+    /// Write through a temporary file and a rename, so Steam never sees a half-written file
+    pub fn save(&self, path: &Path) -> Result<(), InstallerError> {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temp = path.with_file_name(format!(".{}.geode-tmp", file_name));
+
+        let mut file = File::create(&temp)?;
+        file.write_all(self.to_string().as_bytes())?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, InstallerError> {
+        let mut parser = Parser { tokens: Tokenizer::new(text) };
+        parser.block(0)
+    }
+
+    /// Load a file and apply its `#include` and `#base` directives, which name files
+    /// relative to it. The result is for reading; saving it would inline those files.
+    pub fn load_resolved(path: &Path) -> Result<Self, InstallerError> {
+        Self::load_nested(path, &mut Vec::new())
+    }
+
+    fn load_nested(path: &Path, stack: &mut Vec<PathBuf>) -> Result<Self, InstallerError> {
+        let canonical = path.canonicalize()?;
+        if stack.contains(&canonical) {
+            return Err(InstallerError::Vdf(format!("{:?} includes itself", path)));
+        }
+        if stack.len() >= MAX_INCLUDE_DEPTH {
+            return Err(InstallerError::Vdf(format!("{:?}: includes nested too deeply", path)));
+        }
+        stack.push(canonical);
+
+        let mut document = Self::load(path)?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let directives: Vec<Entry> = document.entries.iter()
+            .filter(|entry| entry.is_directive() && entry.applies())
+            .cloned()
+            .collect();
+        document.entries.retain(|entry| !entry.is_directive());
+
+        for directive in directives {
+            let Some(file) = directive.value.as_str() else {
+                continue;
+            };
+            let other = Self::load_nested(&dir.join(file), stack)?;
+            if directive.key.eq_ignore_ascii_case("#include") {
+                document.entries.extend(other.entries);
+            } else {
+                document.merge_base(other);
+            }
+        }
+
+        stack.pop();
+        Ok(document)
+    }
+
+    /// Fill in what `base` has and this document lacks, block by block
+    fn merge_base(&mut self, base: Vdf) {
+        for entry in base.entries {
+            let existing = self.entries.iter_mut()
+                .find(|own| own.key.eq_ignore_ascii_case(&entry.key) && own.applies());
+            match (existing, entry.value) {
+                (None, value) => self.entries.push(Entry { value, ..entry }),
+                (Some(Entry { value: VdfValue::Block(own), .. }), VdfValue::Block(base)) => own.merge_base(base),
+                (Some(_), _) => {}
+            }
+        }
+    }
+
+    /// Every entry, including those whose condition doesn't hold
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Entries that apply on this platform
+    fn applicable(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(|entry| entry.applies())
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.applies() && entry.key.eq_ignore_ascii_case(key))
+    }
+
+    pub fn len(&self) -> usize {
+        self.applicable().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Keys and values in file order, duplicates included
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &VdfValue)> {
+        self.applicable().map(|entry| (entry.key.as_str(), &entry.value))
+    }
+
+    /// The first value stored under `key`, ignoring ASCII case like Steam does
+    pub fn get(&self, key: &str) -> Option<&VdfValue> {
+        self.position(key).map(|index| &self.entries[index].value)
+    }
+
+    /// Every value stored under `key`, for keys that repeat
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a VdfValue> {
+        self.applicable()
+            .filter(move |entry| entry.key.eq_ignore_ascii_case(key))
+            .map(|entry| &entry.value)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut VdfValue> {
+        self.position(key).map(|index| &mut self.entries[index].value)
+    }
+
+    pub fn string(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+
+    pub fn block(&self, key: &str) -> Option<&Vdf> {
+        self.get(key)?.as_block()
+    }
+
+    pub fn block_mut(&mut self, key: &str) -> Option<&mut Vdf> {
+        match self.get_mut(key)? {
+            VdfValue::Block(block) => Some(block),
+            VdfValue::String(_) => None,
+        }
+    }
+
+    /// Follow a path of nested blocks, e.g. `["UserLocalConfigStore", "Software"]`
+    pub fn path(&self, keys: &[&str]) -> Option<&Vdf> {
+        keys.iter().try_fold(self, |block, key| block.block(key))
+    }
+
+    /// Follow a path of nested blocks, creating the ones that are missing
+    pub fn path_or_insert(&mut self, keys: &[&str]) -> &mut Vdf {
+        keys.iter().fold(self, |block, key| block.block_or_insert(key))
+    }
+
+    /// The nested block `key`, appended if missing (or replacing a string of that name)
+    pub fn block_or_insert(&mut self, key: &str) -> &mut Vdf {
+        let index = match self.position(key) {
+            Some(index) => index,
+            None => {
+                self.push(key, VdfValue::Block(Vdf::new()));
+                self.entries.len() - 1
+            }
+        };
+
+        let entry = &mut self.entries[index];
+        if let VdfValue::String(_) = entry.value {
+            entry.value = VdfValue::Block(Vdf::new());
+            // The old layout was for a single line
+            entry.format = None;
+        }
+        match &mut entry.value {
+            VdfValue::Block(block) => block,
+            VdfValue::String(_) => unreachable!(),
+        }
+    }
+
+    /// Set the first value stored under `key` to a string, or append one
+    pub fn set_string(&mut self, key: &str, value: &str) {
+        match self.position(key).map(|index| &mut self.entries[index]) {
+            Some(entry) => {
+                if let VdfValue::Block(_) = entry.value {
+                    entry.format = None;
+                }
+                entry.value = VdfValue::String(value.to_string());
+            }
+            None => self.push(key, VdfValue::String(value.to_string())),
+        }
+    }
+
+    /// Append an entry, even if `key` is already present
+    pub fn push(&mut self, key: &str, value: VdfValue) {
+        self.entries.push(Entry::new(key, value));
+    }
+
+    /// Remove the first entry stored under `key`
+    pub fn remove(&mut self, key: &str) -> Option<VdfValue> {
+        let index = self.position(key)?;
+        Some(self.entries.remove(index).value)
+    }
+
+    /// The `AppState` block of an `appmanifest_<app id>.acf`
+    pub fn app_state(&self) -> Option<&Vdf> {
+        self.block("AppState")
+    }
+
+    /// Per-game settings of a `localconfig.vdf`, keyed by app id
+    pub fn local_config_apps(&self) -> Option<&Vdf> {
+        self.path(LOCAL_CONFIG_APPS)
+    }
+
+    pub fn local_config_apps_mut(&mut self) -> &mut Vdf {
+        self.path_or_insert(LOCAL_CONFIG_APPS)
+    }
+
+    /// Children are indented `depth` tabs, the closing brace one less
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = "\t".repeat(depth);
+        for (i, entry) in self.entries.iter().enumerate() {
+            let format = entry.format.as_ref();
+            match format {
+                Some(format) => out.push_str(&format.leading),
+                None if depth == 0 && i == 0 => {}
+                None => {
+                    out.push('\n');
+                    out.push_str(&indent);
+                }
+            }
+
+            write_token(out, format.and_then(|format| format.key.as_ref()), &entry.key);
+
+            match (format, &entry.value) {
+                (Some(format), _) => out.push_str(&format.between),
+                (None, VdfValue::String(_)) => out.push_str("\t\t"),
+                (None, VdfValue::Block(_)) => {
+                    out.push('\n');
+                    out.push_str(&indent);
+                }
+            }
+
+            match &entry.value {
+                VdfValue::String(value) => {
+                    write_token(out, format.and_then(|format| format.value.as_ref()), value);
+                    if let Some(format) = format {
+                        out.push_str(&format.after);
+                    }
+                }
+                VdfValue::Block(block) => {
+                    out.push('{');
+                    block.write(out, depth + 1);
+                    out.push('}');
+                }
+            }
+        }
+
+        match &self.trailing {
+            Some(trailing) => out.push_str(trailing),
+            None => {
+                out.push('\n');
+                out.push_str(&"\t".repeat(depth.saturating_sub(1)));
+            }
+        }
+    }
+}
+
+/// Key path of the per-game settings in `localconfig.vdf`
+const LOCAL_CONFIG_APPS: &[&str] = &["UserLocalConfigStore", "Software", "Valve", "Steam", "apps"];
+
+/// Serializes parsed entries as they were read and new ones in Steam's own layout:
+/// tab indentation, braces on their own lines
+impl fmt::Display for Vdf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        self.write(&mut out, 0);
+        f.write_str(&out)
+    }
+}
+
+/// The original token if it still reads as `text`, otherwise `text` quoted
+fn write_token(out: &mut String, raw: Option<&RawToken>, text: &str) {
+    match raw {
+        Some(raw) if raw.parsed == text => out.push_str(&raw.text),
+        _ => write_quoted(out, text),
+    }
+}
+
+fn write_quoted(out: &mut String, text: &str) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Evaluate a condition such as `$WIN32`, `!$OSX` or `$LINUX || $OSX`; `&&` binds tighter than `||`
+fn evaluate_condition(condition: &str) -> bool {
+    condition.split("||").any(|all| {
+        all.split("&&").all(|term| {
+            let term = term.trim();
+            let (negated, name) = match term.strip_prefix('!') {
+                Some(name) => (true, name.trim()),
+                None => (false, term),
+            };
+            let name = name.strip_prefix('$').unwrap_or(name);
+            PLATFORM_CONDITIONS.iter().any(|known| known.eq_ignore_ascii_case(name)) != negated
+        })
+    })
+}
+
+/// A key or string value as read from the file
+struct Text<'a> {
+    /// The token in the file, quotes and escapes included
+    raw: &'a str,
+    /// Borrowed from the input unless escape sequences had to be decoded
+    value: Cow<'a, str>,
+}
+
+impl Text<'_> {
+    /// The raw token, if writing the value back in the usual quoted form would change it
+    fn unusual_form(&self) -> Option<RawToken> {
+        // Literal tabs, newlines or quotes and unknown escapes all make the token shorter than that
+        let quoted = self.raw.len() == escaped_len(&self.value) + 2 && self.raw.starts_with('"');
+        (!quoted).then(|| RawToken {
+            text: self.raw.to_string(),
+            parsed: self.value.to_string(),
+        })
+    }
+}
+
+/// Length of `text` once escaped by `write_quoted`
+fn escaped_len(text: &str) -> usize {
+    text.len() + text.bytes().filter(|byte| matches!(byte, b'\\' | b'"' | b'\n' | b'\t')).count()
+}
+
+/// Splits KeyValues text into tokens in a single pass over its bytes. Every delimiter is
+/// ASCII, so slicing at them always falls on UTF-8 character boundaries.
+struct Tokenizer<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, bytes: text.as_bytes(), pos: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    /// Source text from `start` up to the current position
+    fn since(&self, start: usize) -> &'a str {
+        &self.text[start..self.pos]
+    }
+
+    /// Skip whitespace and `//` comments, returning them
+    fn trivia(&mut self) -> &'a str {
+        let start = self.pos;
+        loop {
+            self.whitespace();
+            if self.bytes[self.pos..].starts_with(b"//") {
+                self.pos += self.bytes[self.pos..].iter().position(|&byte| byte == b'\n').unwrap_or(self.bytes.len() - self.pos);
+            } else {
+                break;
+            }
+        }
+        self.since(start)
+    }
+
+    /// ASCII whitespace, plus a byte order mark
+    fn whitespace(&mut self) {
+        loop {
+            match self.peek() {
+                Some(byte) if byte.is_ascii_whitespace() => self.pos += 1,
+                Some(0xef) if self.bytes[self.pos..].starts_with(BOM) => self.pos += BOM.len(),
+                _ => return,
+            }
+        }
+    }
+
+    /// A quoted string with `\"`-style escapes, or a bare word
+    fn text(&mut self) -> Result<Text<'a>, InstallerError> {
+        let start = self.pos;
+        if self.peek() != Some(b'"') {
+            let length = self.bytes[start..].iter()
+                .position(|&byte| byte.is_ascii_whitespace() || matches!(byte, b'{' | b'}' | b'"' | b'['))
+                .unwrap_or(self.bytes.len() - start);
+            self.pos += length;
+            let raw = self.since(start);
+            return Ok(Text { raw, value: Cow::Borrowed(raw) });
+        }
+
+        self.pos += 1;
+        let content_start = self.pos;
+        let mut decoded: Option<String> = None;
+        // Start of the input not yet copied into `decoded`
+        let mut copied = content_start;
+
+        loop {
+            let Some(offset) = self.bytes[self.pos..].iter().position(|&byte| byte == b'"' || byte == b'\\') else {
+                return Err(self.error(start, "unterminated string"));
+            };
+            self.pos += offset;
+
+            if self.bytes[self.pos] == b'"' {
+                let value = match decoded {
+                    Some(mut decoded) => {
+                        decoded.push_str(&self.text[copied..self.pos]);
+                        Cow::Owned(decoded)
+                    }
+                    None => Cow::Borrowed(&self.text[content_start..self.pos]),
+                };
+                self.pos += 1;
+                return Ok(Text { raw: self.since(start), value });
+            }
+
+            let unescaped = match self.bytes.get(self.pos + 1) {
+                Some(b'n') => '\n',
+                Some(b't') => '\t',
+                Some(b'\\') => '\\',
+                Some(b'"') => '"',
+                // Unknown escapes are kept, e.g. in unescaped Windows paths
+                Some(_) => {
+                    self.pos += 1;
+                    continue;
+                }
+                None => return Err(self.error(start, "unterminated string")),
+            };
+            let decoded = decoded.get_or_insert_with(String::new);
+            decoded.push_str(&self.text[copied..self.pos]);
+            decoded.push(unescaped);
+            self.pos += 2;
+            copied = self.pos;
+        }
+    }
+
+    /// `[...]`, returning the condition inside the brackets
+    fn condition(&mut self) -> Result<&'a str, InstallerError> {
+        let start = self.pos;
+        let Some(length) = self.bytes[start..].iter().position(|&byte| byte == b']' || byte == b'\n') else {
+            return Err(self.error(start, "unterminated condition"));
+        };
+        if self.bytes[start + length] == b'\n' {
+            return Err(self.error(start, "unterminated condition"));
+        }
+        self.pos += length + 1;
+        Ok(self.text[start + 1..start + length].trim())
+    }
+
+    /// An error pointing at the byte at `pos`
+    fn error(&self, pos: usize, message: &str) -> InstallerError {
+        let before = &self.text[..pos];
+        let line = 1 + before.bytes().filter(|&byte| byte == b'\n').count();
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = 1 + before[line_start..].chars().count();
+        InstallerError::Vdf(format!("line {}, column {}: {}", line, column, message))
+    }
+}
+
+const BOM: &[u8] = "\u{feff}".as_bytes();
+
+struct Parser<'a> {
+    tokens: Tokenizer<'a>,
+}
+
+impl Parser<'_> {
+    /// Entries up to the closing brace of a block at `depth`, or the end of the file at depth 0
+    fn block(&mut self, depth: usize) -> Result<Vdf, InstallerError> {
+        if depth > MAX_DEPTH {
+            return Err(self.tokens.error(self.tokens.pos, "blocks nested too deeply"));
+        }
+        let mut block = Vdf::new();
+        loop {
+            let leading = self.tokens.trivia();
+            let start = self.tokens.pos;
+            match self.tokens.peek() {
+                None if depth > 0 => return Err(self.tokens.error(start, "missing '}' at end of file")),
+                None => {
+                    block.trailing = Some(leading.to_string());
+                    return Ok(block);
+                }
+                Some(b'}') if depth > 0 => {
+                    self.tokens.pos += 1;
+                    block.trailing = Some(leading.to_string());
+                    return Ok(block);
+                }
+                Some(b'}') => return Err(self.tokens.error(start, "'}' without a matching '{'")),
+                Some(b'{') => return Err(self.tokens.error(start, "expected a key before '{'")),
+                Some(b'[') => return Err(self.tokens.error(start, "expected a key before a condition")),
+                Some(_) => block.entries.push(self.entry(leading, depth)?),
+            }
+        }
+    }
+
+    /// A key followed by a string or block, each optionally guarded by a condition:
+    /// `"key" [$WIN32] "value"`, `"key" "value" [$WIN32]` or `"key" [$WIN32] { ... }`
+    fn entry(&mut self, leading: &str, depth: usize) -> Result<Entry, InstallerError> {
+        let key = self.tokens.text()?;
+
+        let between_start = self.tokens.pos;
+        self.tokens.trivia();
+        let mut condition = None;
+        if self.tokens.peek() == Some(b'[') {
+            condition = Some(self.tokens.condition()?.to_string());
+            self.tokens.trivia();
+        }
+        let between = self.tokens.since(between_start);
+
+        let start = self.tokens.pos;
+        let (value, raw_value) = match self.tokens.peek() {
+            Some(b'{') => {
+                self.tokens.pos += 1;
+                (VdfValue::Block(self.block(depth + 1)?), None)
+            }
+            Some(b'}') | Some(b'[') | None => {
+                return Err(self.tokens.error(start, &format!("missing value for '{}'", key.value)));
+            }
+            Some(_) => {
+                let value = self.tokens.text()?;
+                let raw = value.unusual_form();
+                (VdfValue::String(value.value.into_owned()), raw)
+            }
+        };
+
+        // A condition after a string value belongs to this entry, not the next one
+        let mut after = "";
+        if let VdfValue::String(_) = value {
+            let after_start = self.tokens.pos;
+            self.tokens.whitespace();
+            if self.tokens.peek() == Some(b'[') {
+                if condition.is_some() {
+                    return Err(self.tokens.error(self.tokens.pos, "an entry can only have one condition"));
+                }
+                condition = Some(self.tokens.condition()?.to_string());
+                after = self.tokens.since(after_start);
+            } else {
+                self.tokens.pos = after_start;
+            }
+        }
+
+        Ok(Entry {
+            format: Some(EntryFormat {
+                leading: leading.to_string(),
+                key: key.unusual_form(),
+                between: between.to_string(),
+                value: raw_value,
+                after: after.to_string(),
+            }),
+            key: key.value.into_owned(),
+            value,
+            condition,
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use crate::errors::InstallerError;
+use homedir::my_home;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// A Wine program, or the wineserver, running in a particular prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixProcess {
+    pub pid: u32,
+    /// Executable name, e.g. `GeometryDash.exe` or `wineserver`
+    pub name: String,
+}
+
+/// Finds Wine processes by scanning `/proc`.
+///
+/// A process belongs to a prefix when its `WINEPREFIX` (defaulting to `~/.wine`) or its
+/// working directory points there. The wineserver itself changes into
+/// `/tmp/.wine-<uid>/server-<dev>-<inode>`, named after the prefix directory, so that
+/// is recognized as well.
+pub struct WineProcesses {
+    proc_root: PathBuf,
+}
+
+impl Default for WineProcesses {
+    fn default() -> Self {
+        Self::at(PathBuf::from("/proc"))
+    }
+}
+
+impl WineProcesses {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn at(proc_root: PathBuf) -> Self {
+        Self { proc_root }
+    }
+
+    pub fn using_prefix(&self, prefix: &Path) -> Result<Vec<PrefixProcess>, InstallerError> {
+        let prefix = prefix.canonicalize()?;
+        let server_dir = fs::metadata(&prefix)
+            .map(|meta| format!("server-{:x}-{:x}", meta.dev(), meta.ino()))?;
+        let own_pid = std::process::id();
+
+        let mut processes = Vec::new();
+        for entry in fs::read_dir(&self.proc_root)? {
+            let entry = entry?;
+            let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+                continue;
+            };
+            if pid == own_pid {
+                continue;
+            }
+
+            // Processes can exit mid-scan or belong to other users; skip what can't be read
+            let dir = entry.path();
+            let Some(name) = wine_process_name(&dir) else {
+                continue;
+            };
+
+            let cwd = fs::read_link(dir.join("cwd")).ok();
+            let in_prefix = cwd.as_ref().is_some_and(|cwd| {
+                cwd.starts_with(&prefix) || cwd.file_name().is_some_and(|name| *name == *server_dir)
+            });
+
+            if in_prefix || wine_prefix_of(&dir).is_some_and(|wine_prefix| wine_prefix == prefix) {
+                processes.push(PrefixProcess { pid, name });
+            }
+        }
+
+        processes.sort_by_key(|process| process.pid);
+        Ok(processes)
+    }
+
+    /// Running Steam clients. Steam writes its configuration, including launch options,
+    /// back to disk when it exits.
+    pub fn steam_clients(&self) -> Result<Vec<PrefixProcess>, InstallerError> {
+        let own_pid = std::process::id();
+
+        let mut processes = Vec::new();
+        for entry in fs::read_dir(&self.proc_root)? {
+            let entry = entry?;
+            let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+                continue;
+            };
+            if pid == own_pid {
+                continue;
+            }
+
+            let Ok(comm) = fs::read_to_string(entry.path().join("comm")) else {
+                continue;
+            };
+            if comm.trim_end() == "steam" {
+                processes.push(PrefixProcess { pid, name: "steam".to_string() });
+            }
+        }
+
+        processes.sort_by_key(|process| process.pid);
+        Ok(processes)
+    }
+}
+
+/// The executable name if the process is part of Wine: the wineserver, a loader or a Windows program
+fn wine_process_name(dir: &Path) -> Option<String> {
+    let comm = fs::read_to_string(dir.join("comm")).ok()?;
+    let comm = comm.trim_end();
+    if comm == "wineserver" {
+        return Some(comm.to_string());
+    }
+
+    let cmdline = fs::read(dir.join("cmdline")).ok()?;
+    let arg0 = String::from_utf8_lossy(cmdline.split(|&byte| byte == 0).next()?).into_owned();
+    // Windows programs show up with their Windows path, e.g. `Z:\...\GeometryDash.exe`
+    let name = arg0.rsplit(['/', '\\']).next().unwrap_or(&arg0).to_string();
+
+    let lower = name.to_ascii_lowercase();
+    (lower.ends_with(".exe") || lower.starts_with("wine")).then_some(name)
+}
+
+fn wine_prefix_of(dir: &Path) -> Option<PathBuf> {
+    let environ = fs::read(dir.join("environ")).ok()?;
+    let prefix = environ.split(|&byte| byte == 0)
+        .find_map(|var| var.strip_prefix(b"WINEPREFIX="))
+        .map(|value| PathBuf::from(String::from_utf8_lossy(value).into_owned()))
+        .or_else(|| Some(my_home().ok()??.join(".wine")))?;
+
+    prefix.canonicalize().ok()
+}
@@ -0,0 +1,546 @@
+use crate::errors::InstallerError;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds between the FILETIME epoch (1601-01-01) and the Unix epoch
+const FILETIME_EPOCH_OFFSET: u64 = 11_644_473_600;
+/// Wine wraps long hex values after this many columns
+const HEX_LINE_WIDTH: usize = 76;
+
+/// A Wine registry file such as `user.reg` or `system.reg`.
+///
+/// Everything is kept alongside the text it was parsed from, so serializing
+/// reproduces untouched parts byte for byte and only rewrites what was changed.
+#[derive(Debug, Clone)]
+pub struct RegistryFile {
+    /// `WINE REGISTRY Version 2`, comments and `#arch=` before the first key
+    header: Vec<String>,
+    sections: Vec<Section>,
+    /// Blank lines and comments after the last value
+    trailer: Vec<String>,
+    newline: &'static str,
+}
+
+/// One `[key\\path] timestamp` block and its values
+#[derive(Debug, Clone)]
+pub struct Section {
+    name: String,
+    timestamp: Option<u64>,
+    /// Blank lines and comments between the previous key and this one
+    leading: Vec<String>,
+    header: Option<String>,
+    lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone)]
+enum Line {
+    /// A value, with the text it was parsed from until it is changed
+    Value { name: String, value: RegValue, raw: Option<String> },
+    /// Metadata such as `#time=` or `#class=`, blank lines and comments
+    Other(String),
+}
+
+/// Data of a registry value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegValue {
+    /// `"text"` (REG_SZ)
+    String(String),
+    /// `str(N):"text"`, e.g. REG_EXPAND_SZ (2) or REG_MULTI_SZ (7)
+    TypedString { kind: u32, value: String },
+    /// `dword:0000002a`
+    Dword(u32),
+    /// `hex:..` (REG_BINARY) or `hex(N):..` for any other type
+    Hex { kind: Option<u32>, bytes: Vec<u8> },
+}
+
+impl RegValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            RegValue::String(value) | RegValue::TypedString { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl RegistryFile {
+    pub fn load(path: &Path) -> Result<Self, InstallerError> {
+        Self::parse(&fs::read_to_string(path)?).map_err(|e| match e {
+            InstallerError::Registry(message) => InstallerError::Registry(format!("{:?}, {}", path, message)),
+            e => e,
+        })
+    }
+
+    /// Write through a temporary file and a rename, so a crash never leaves a half-written registry
+    pub fn save(&self, path: &Path) -> Result<(), InstallerError> {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temp = path.with_file_name(format!(".{}.geode-tmp", file_name));
+
+        let mut file = File::create(&temp)?;
+        file.write_all(self.to_string().as_bytes())?;
+        file.sync_all()?;
+        drop(file);
+
+        if let Err(e) = fs::rename(&temp, path) {
+            let _ = fs::remove_file(&temp);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, InstallerError> {
+        let mut registry = RegistryFile {
+            header: Vec::new(),
+            sections: Vec::new(),
+            trailer: Vec::new(),
+            newline: if text.contains("\r\n") { "\r\n" } else { "\n" },
+        };
+        // Blank lines and comments whose owner depends on what comes next
+        let mut pending: Vec<String> = Vec::new();
+
+        let mut physical = text.split_inclusive('\n').enumerate().peekable();
+        while let Some((index, first)) = physical.next() {
+            let line_number = index + 1;
+            let mut raw = first.to_string();
+            let mut logical = trim_line_ending(first).to_string();
+
+            // Long hex values continue on the next line after a trailing backslash
+            while logical.ends_with('\\') && logical.contains("=hex") {
+                let Some((_, next)) = physical.next() else { break };
+                logical.pop();
+                logical.push_str(trim_line_ending(next).trim_start());
+                raw.push_str(next);
+            }
+
+            let trimmed = logical.trim_start();
+            if trimmed.starts_with('[') {
+                let (name, timestamp) = parse_section_header(trimmed)
+                    .map_err(|message| parse_error(line_number, &message))?;
+                registry.sections.push(Section {
+                    name,
+                    timestamp,
+                    leading: std::mem::take(&mut pending),
+                    header: Some(raw),
+                    lines: Vec::new(),
+                });
+                continue;
+            }
+
+            let Some(section) = registry.sections.last_mut() else {
+                registry.header.push(raw);
+                continue;
+            };
+
+            if trimmed.is_empty() || trimmed.starts_with(';') {
+                pending.push(raw);
+                continue;
+            }
+            section.lines.extend(pending.drain(..).map(Line::Other));
+
+            if trimmed.starts_with('#') {
+                section.lines.push(Line::Other(raw));
+            } else {
+                let (name, value) = parse_value_line(trimmed)
+                    .map_err(|message| parse_error(line_number, &message))?;
+                section.lines.push(Line::Value { name, value, raw: Some(raw) });
+            }
+        }
+
+        registry.trailer = pending;
+        Ok(registry)
+    }
+
+    /// Architecture from the `#arch=` header line, e.g. `win64`
+    pub fn arch(&self) -> Option<&str> {
+        self.header.iter()
+            .find_map(|line| trim_line_ending(line).strip_prefix("#arch="))
+    }
+
+    pub fn sections(&self) -> impl Iterator<Item = &Section> {
+        self.sections.iter()
+    }
+
+    /// Key paths use single backslashes and match case-insensitively, like Windows
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|section| section.is(name))
+    }
+
+    pub fn section_mut(&mut self, name: &str) -> Option<&mut Section> {
+        self.sections.iter_mut().find(|section| section.is(name))
+    }
+
+    /// The key called `name`, appended to the end of the file if it doesn't exist yet
+    pub fn section_or_insert(&mut self, name: &str) -> &mut Section {
+        let index = match self.sections.iter().position(|section| section.is(name)) {
+            Some(index) => index,
+            None => {
+                self.sections.push(Section::new(name, self.newline));
+                self.sections.len() - 1
+            }
+        };
+        &mut self.sections[index]
+    }
+
+    /// Remove a key with all its values, returning whether it existed
+    pub fn remove_section(&mut self, name: &str) -> bool {
+        let count = self.sections.len();
+        self.sections.retain(|section| !section.is(name));
+        self.sections.len() != count
+    }
+}
+
+impl fmt::Display for RegistryFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        for line in &self.header {
+            out.push_str(line);
+        }
+        for section in &self.sections {
+            section.write(&mut out, self.newline);
+        }
+        for line in &self.trailer {
+            push_raw(&mut out, line, self.newline);
+        }
+        f.write_str(&out)
+    }
+}
+
+impl Section {
+    fn new(name: &str, newline: &str) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap();
+        let filetime = (now.as_secs() + FILETIME_EPOCH_OFFSET) * 10_000_000 + u64::from(now.subsec_nanos() / 100);
+
+        Self {
+            name: name.to_string(),
+            timestamp: Some(now.as_secs()),
+            // Wine separates keys with a blank line
+            leading: vec![newline.to_string()],
+            header: None,
+            lines: vec![Line::Other(format!("#time={:x}{}", filetime, newline))],
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Last modification time as written after the key name, in Unix seconds
+    pub fn timestamp(&self) -> Option<u64> {
+        self.timestamp
+    }
+
+    /// Look up a value; the default value is called `""`
+    pub fn get(&self, name: &str) -> Option<&RegValue> {
+        self.values()
+            .find(|(value_name, _)| value_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = (&str, &RegValue)> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Value { name, value, .. } => Some((name.as_str(), value)),
+            Line::Other(_) => None,
+        })
+    }
+
+    pub fn has_values(&self) -> bool {
+        self.values().next().is_some()
+    }
+
+    /// Set a value in place, or append it after the existing ones. Returns the old value.
+    pub fn set(&mut self, name: &str, value: RegValue) -> Option<RegValue> {
+        for line in &mut self.lines {
+            if let Line::Value { name: existing, value: old, raw } = line
+                && existing.eq_ignore_ascii_case(name)
+            {
+                *raw = None;
+                return Some(std::mem::replace(old, value));
+            }
+        }
+
+        let position = self.lines.iter()
+            .rposition(|line| matches!(line, Line::Value { .. }))
+            .map(|index| index + 1)
+            .unwrap_or_else(|| self.lines.iter().take_while(|line| is_metadata(line)).count());
+        self.lines.insert(position, Line::Value { name: name.to_string(), value, raw: None });
+        None
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<RegValue> {
+        let index = self.lines.iter().position(|line| {
+            matches!(line, Line::Value { name: existing, .. } if existing.eq_ignore_ascii_case(name))
+        })?;
+
+        match self.lines.remove(index) {
+            Line::Value { value, .. } => Some(value),
+            Line::Other(_) => None,
+        }
+    }
+
+    fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    fn write(&self, out: &mut String, newline: &str) {
+        for line in &self.leading {
+            push_raw(out, line, newline);
+        }
+
+        match &self.header {
+            Some(header) => push_raw(out, header, newline),
+            None => {
+                start_line(out, newline);
+                out.push('[');
+                out.push_str(&escape(&self.name, "[]"));
+                out.push(']');
+                if let Some(timestamp) = self.timestamp {
+                    out.push_str(&format!(" {}", timestamp));
+                }
+                out.push_str(newline);
+            }
+        }
+
+        for line in &self.lines {
+            match line {
+                Line::Value { raw: Some(raw), .. } | Line::Other(raw) => push_raw(out, raw, newline),
+                Line::Value { name, value, raw: None } => {
+                    start_line(out, newline);
+                    write_value(out, name, value, newline);
+                    out.push_str(newline);
+                }
+            }
+        }
+    }
+}
+
+fn is_metadata(line: &Line) -> bool {
+    matches!(line, Line::Other(text) if text.starts_with('#'))
+}
+
+fn write_value(out: &mut String, name: &str, value: &RegValue, newline: &str) {
+    if name.is_empty() {
+        out.push('@');
+    } else {
+        out.push_str(&format!("\"{}\"", escape(name, "")));
+    }
+    out.push('=');
+
+    match value {
+        RegValue::String(value) => out.push_str(&format!("\"{}\"", escape(value, ""))),
+        RegValue::TypedString { kind, value } => out.push_str(&format!("str({}):\"{}\"", kind, escape(value, ""))),
+        RegValue::Dword(value) => out.push_str(&format!("dword:{:08x}", value)),
+        RegValue::Hex { kind, bytes } => {
+            match kind {
+                Some(kind) => out.push_str(&format!("hex({:x}):", kind)),
+                None => out.push_str("hex:"),
+            }
+
+            let mut column = out.len() - out.rfind('\n').map(|pos| pos + 1).unwrap_or(0);
+            for (i, byte) in bytes.iter().enumerate() {
+                out.push_str(&format!("{:02x}", byte));
+                column += 2;
+                if i + 1 < bytes.len() {
+                    out.push(',');
+                    column += 1;
+                    if column > HEX_LINE_WIDTH {
+                        out.push('\\');
+                        out.push_str(newline);
+                        out.push_str("  ");
+                        column = 2;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Append text taken from the original file, which may lack a final line ending
+fn push_raw(out: &mut String, raw: &str, newline: &str) {
+    start_line(out, newline);
+    out.push_str(raw);
+}
+
+fn start_line(out: &mut String, newline: &str) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push_str(newline);
+    }
+}
+
+fn trim_line_ending(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+fn parse_error(line: usize, message: &str) -> InstallerError {
+    InstallerError::Registry(format!("line {}: {}", line, message))
+}
+
+/// `[Software\\Wine\\DllOverrides] 1700000000` → name and timestamp
+fn parse_section_header(line: &str) -> Result<(String, Option<u64>), String> {
+    let (name, rest) = parse_escaped(&line[1..], Some(']'))
+        .ok_or("unterminated key name")?;
+
+    let rest = rest.trim();
+    let timestamp = if rest.is_empty() {
+        None
+    } else {
+        Some(rest.parse().map_err(|_| format!("invalid key timestamp '{}'", rest))?)
+    };
+    Ok((name, timestamp))
+}
+
+/// `"name"=<data>` or `@=<data>`
+fn parse_value_line(line: &str) -> Result<(String, RegValue), String> {
+    let (name, rest) = if let Some(rest) = line.strip_prefix('@') {
+        (String::new(), rest)
+    } else {
+        let quoted = line.strip_prefix('"').ok_or("expected a quoted value name")?;
+        parse_escaped(quoted, Some('"')).ok_or("unterminated value name")?
+    };
+
+    let data = rest.trim_start().strip_prefix('=').ok_or("expected '=' after the value name")?;
+    Ok((name, parse_data(data.trim())?))
+}
+
+fn parse_data(data: &str) -> Result<RegValue, String> {
+    if let Some(quoted) = data.strip_prefix('"') {
+        return Ok(RegValue::String(parse_string(quoted)?));
+    }
+
+    if let Some(rest) = data.strip_prefix("str(") {
+        let (kind, quoted) = rest.split_once("):").ok_or("expected 'str(N):'")?;
+        let kind = u32::from_str_radix(kind, 16).map_err(|_| format!("invalid string type '{}'", kind))?;
+        let quoted = quoted.strip_prefix('"').ok_or("expected a quoted string")?;
+        return Ok(RegValue::TypedString { kind, value: parse_string(quoted)? });
+    }
+
+    if let Some(hex) = data.strip_prefix("dword:") {
+        let value = u32::from_str_radix(hex.trim(), 16).map_err(|_| format!("invalid dword '{}'", hex))?;
+        return Ok(RegValue::Dword(value));
+    }
+
+    let (kind, bytes) = if let Some(bytes) = data.strip_prefix("hex:") {
+        (None, bytes)
+    } else if let Some(rest) = data.strip_prefix("hex(") {
+        let (kind, bytes) = rest.split_once("):").ok_or("expected 'hex(N):'")?;
+        let kind = u32::from_str_radix(kind, 16).map_err(|_| format!("invalid value type '{}'", kind))?;
+        (Some(kind), bytes)
+    } else {
+        return Err(format!("unsupported value data '{}'", data));
+    };
+
+    let bytes = bytes.split(',')
+        .map(str::trim)
+        .filter(|byte| !byte.is_empty())
+        .map(|byte| u8::from_str_radix(byte, 16).map_err(|_| format!("invalid hex byte '{}'", byte)))
+        .collect::<Result<_, _>>()?;
+    Ok(RegValue::Hex { kind, bytes })
+}
+
+/// Body of a quoted string, which must be the last thing on the line
+fn parse_string(quoted: &str) -> Result<String, String> {
+    let (value, rest) = parse_escaped(quoted, Some('"')).ok_or("unterminated string")?;
+    if !rest.trim().is_empty() {
+        return Err(format!("unexpected '{}' after string", rest.trim()));
+    }
+    Ok(value)
+}
+
+/// Unescape up to the first unescaped `end`, returning the text and what follows it.
+/// Understands the same escapes as Wine: `\n`-style letters, octal `\0`-`\777` and
+/// UTF-16 code units written as `\xNNNN`.
+fn parse_escaped(text: &str, end: Option<char>) -> Option<(String, &str)> {
+    let mut units: Vec<u16> = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        if Some(c) == end {
+            return Some((String::from_utf16_lossy(&units), &text[index + c.len_utf8()..]));
+        }
+        if c != '\\' {
+            units.extend(c.encode_utf16(&mut [0; 2]).iter());
+            continue;
+        }
+
+        let (_, escaped) = chars.next()?;
+        let (radix, max_digits) = match escaped {
+            '0'..='7' => (8, 3),
+            'x' => (16, 4),
+            _ => {
+                let decoded = match escaped {
+                    'a' => '\x07',
+                    'b' => '\x08',
+                    'e' => '\x1b',
+                    'f' => '\x0c',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'v' => '\x0b',
+                    other => other,
+                };
+                units.extend(decoded.encode_utf16(&mut [0; 2]).iter());
+                continue;
+            }
+        };
+
+        let mut code: u32 = 0;
+        let mut digits = 0;
+        if radix == 8 {
+            code = escaped.to_digit(8)?;
+            digits = 1;
+        }
+        while digits < max_digits
+            && let Some(digit) = chars.peek().and_then(|(_, c)| c.to_digit(radix))
+        {
+            code = code * radix + digit;
+            digits += 1;
+            chars.next();
+        }
+
+        match digits {
+            // A lone `\x` stands for itself
+            0 => units.push(u16::from(b'x')),
+            _ => units.push(code as u16),
+        }
+    }
+
+    end.is_none().then(|| (String::from_utf16_lossy(&units), ""))
+}
+
+/// Escape text the way Wine writes it, plus any characters in `extra`
+pub fn escape(text: &str, extra: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\0' => escaped.push_str("\\0"),
+            c if extra.contains(c) => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_ascii_control() || !c.is_ascii() => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    escaped.push_str(&format!("\\x{:04x}", unit));
+                }
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Undo `escape`, e.g. for key paths copied from a registry file
+pub fn unescape(text: &str) -> String {
+    parse_escaped(text, None)
+        .map(|(value, _)| value)
+        .unwrap_or_else(|| text.to_string())
+}
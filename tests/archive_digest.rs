@@ -0,0 +1,70 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use geode_cli_installer::errors::InstallerError;
+    use geode_cli_installer::utils::geode_installer::{self, ArchiveDigest};
+    use serde_json::json;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// SHA-256 of the downloaded `archive`
+    const SHA256: &str = "0eb3e36bfb24dcd9bb1d1bece1531216b59539a8fde17ee80224af0653c92aa3";
+
+    fn downloaded(name: &str) -> PathBuf {
+        let zip_path = common::temp_dir(&format!("digest-{}", name)).join("geode_temp.zip");
+        fs::write(&zip_path, b"archive").unwrap();
+        zip_path
+    }
+
+    #[test]
+    fn test_matching_archive_is_kept() {
+        let zip_path = downloaded("match");
+        let digest = ArchiveDigest { sha256: SHA256.to_uppercase(), size: Some(7) };
+
+        digest.verify(&zip_path).unwrap();
+        assert!(zip_path.exists());
+    }
+
+    #[test]
+    fn test_mismatched_archive_is_deleted() {
+        for (name, digest, expected) in [
+            ("size", ArchiveDigest { sha256: SHA256.to_string(), size: Some(8) }, "8 bytes"),
+            ("sha256", ArchiveDigest { sha256: "0".repeat(64), size: Some(7) }, "sha256:0000"),
+            ("unsized", ArchiveDigest { sha256: "0".repeat(64), size: None }, "sha256:0000"),
+        ] {
+            let zip_path = downloaded(name);
+            match digest.verify(&zip_path) {
+                Err(InstallerError::ChecksumMismatch { expected: message, .. }) => {
+                    assert!(message.starts_with(expected), "{}: {}", name, message)
+                }
+                other => panic!("{}: expected ChecksumMismatch, got {:?}", name, other),
+            }
+            assert!(!zip_path.exists(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_parse_asset_digest() {
+        let release = json!({
+            "tag_name": "v4.2.0",
+            "assets": [
+                { "name": "geode-v4.2.0-mac.zip", "size": 10, "digest": "sha256:aaaa" },
+                { "name": "geode-v4.2.0-win.zip", "size": 7, "digest": format!("sha256:{}", SHA256) },
+                { "name": "geode-v4.2.0-android64.zip", "size": 12, "digest": "sha256:bbbb" },
+            ],
+        });
+
+        let digest = geode_installer::parse_asset_digest(&release, "geode-v4.2.0-win.zip").unwrap();
+        assert_eq!(digest.sha256, SHA256);
+        assert_eq!(digest.size, Some(7));
+
+        assert!(geode_installer::parse_asset_digest(&release, "geode-v4.1.0-win.zip").is_none());
+        // Releases published before GitHub computed digests have none
+        let undigested = json!({ "assets": [{ "name": "geode-v4.2.0-win.zip", "size": 7, "digest": null }] });
+        assert!(geode_installer::parse_asset_digest(&undigested, "geode-v4.2.0-win.zip").is_none());
+        let other_algorithm = json!({ "assets": [{ "name": "geode-v4.2.0-win.zip", "digest": "sha512:cccc" }] });
+        assert!(geode_installer::parse_asset_digest(&other_algorithm, "geode-v4.2.0-win.zip").is_none());
+    }
+}
@@ -0,0 +1,45 @@
+//! Fixtures shared by the integration tests; each test file uses only some of them
+#![allow(dead_code)]
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+
+/// A `user.reg` as Wine creates it, without any DLL overrides
+pub const USER_REG: &str = "WINE REGISTRY Version 2\n;; All keys relative to \\\\User\n\n#arch=win64\n\n\
+[Software\\\\Wine] 1700000000\n#time=1da1\n\"Version\"=\"win10\"\n";
+
+/// An empty `geode-<name>-<pid>` directory in the system temp directory, cleared of earlier runs
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("geode-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A Wine prefix with `USER_REG` and an empty game directory, as `(root, prefix, game_dir)`
+pub fn setup(name: &str) -> (PathBuf, PathBuf, PathBuf) {
+    let root = temp_dir(name);
+    let prefix = root.join("pfx");
+    let game_dir = root.join("Geometry Dash");
+    fs::create_dir_all(&prefix).unwrap();
+    fs::create_dir_all(&game_dir).unwrap();
+    fs::write(prefix.join("user.reg"), USER_REG).unwrap();
+    (root, prefix, game_dir)
+}
+
+pub fn write_archive(path: &Path, entries: &[(&str, &[u8])]) {
+    let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+    for (name, content) in entries {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(content).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+pub fn write_script(path: &Path, content: &str) {
+    fs::write(path, content).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+}
@@ -0,0 +1,81 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use geode_cli_installer::utils::compat_tools::{self, CompatToolInfo};
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn config(mapping: &str) -> String {
+        format!(
+            "\"InstallConfigStore\"\n{{\n\t\"Software\"\n\t{{\n\t\t\"Valve\"\n\t\t{{\n\t\t\t\"Steam\"\n\t\t\t{{\n\
+\t\t\t\t\"CompatToolMapping\"\n\t\t\t\t{{\n{}\t\t\t\t}}\n\t\t\t}}\n\t\t}}\n\t}}\n}}\n",
+            mapping
+        )
+    }
+
+    fn mapping(app_id: &str, tool: &str) -> String {
+        format!("\t\t\t\t\t\"{}\"\n\t\t\t\t\t{{\n\t\t\t\t\t\t\"name\"\t\t\"{}\"\n\t\t\t\t\t\t\"config\"\t\t\"\"\n\
+\t\t\t\t\t\t\"priority\"\t\t\"250\"\n\t\t\t\t\t}}\n", app_id, tool)
+    }
+
+    fn setup() -> PathBuf {
+        let root = common::temp_dir("compat-tools");
+
+        for (dir, version) in [("Proton 8.0", "1700000000 proton-8.0-5"), ("Proton - Experimental", "1716829420 experimental-9.0-20240520")] {
+            let path = root.join("steamapps/common").join(dir);
+            fs::create_dir_all(&path).unwrap();
+            fs::write(path.join("version"), version).unwrap();
+        }
+        fs::create_dir_all(root.join("steamapps/common/Celeste")).unwrap();
+
+        let custom = root.join("compatibilitytools.d/GE-Proton9-20");
+        fs::create_dir_all(&custom).unwrap();
+        fs::write(
+            custom.join("compatibilitytool.vdf"),
+            "\"compatibilitytools\"\n{\n\t\"compat_tools\"\n\t{\n\t\t\"GE-Proton9-20\"\n\t\t{\n\t\t\t\"install_path\"\t\t\".\"\n\
+\t\t\t\"display_name\"\t\t\"GE-Proton9-20\"\n\t\t\t\"from_oslist\"\t\t\"windows\"\n\t\t\t\"to_oslist\"\t\t\"linux\"\n\t\t}\n\t}\n}\n",
+        ).unwrap();
+
+        let compatdata = root.join("steamapps/compatdata/322170");
+        fs::create_dir_all(compatdata.join("pfx")).unwrap();
+        fs::write(compatdata.join("version"), "9.0-203\n").unwrap();
+        fs::create_dir_all(root.join("config")).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_reports_configured_tool_and_prefix() {
+        let root = setup();
+        let tools = compat_tools::installed_tools(&root, &[root.join("steamapps")]);
+        let names: Vec<&str> = tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(names, ["proton_experimental", "proton_8", "GE-Proton9-20"]);
+        assert_eq!(tools[1].version.as_deref(), Some("8.0-5"));
+        assert!(tools[2].custom);
+
+        let compatdata = root.join("steamapps/compatdata/322170");
+        fs::write(root.join("config/config.vdf"), config(&(mapping("0", "proton_experimental") + &mapping("322170", "proton_8")))).unwrap();
+        let info = CompatToolInfo::read(&root, "322170", &tools, Some(&compatdata));
+        assert_eq!(info.tool_name.as_deref(), Some("proton_8"));
+        assert_eq!(info.prefix_version.as_deref(), Some("9.0-203"));
+        assert_eq!(info.summary("GD"), "GD uses Proton 8.0 (prefix created with 9.0)");
+        let warnings = info.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("created by Proton 9.0, which is newer than Proton 8.0"), "{}", warnings[0]);
+
+        // Games without their own entry use the tool chosen for all games
+        fs::write(root.join("config/config.vdf"), config(&mapping("0", "GE-Proton9-20"))).unwrap();
+        let info = CompatToolInfo::read(&root, "322170", &tools, Some(&compatdata));
+        assert_eq!(info.summary("GD"), "GD uses GE-Proton9-20 (prefix created with 9.0)");
+        assert!(info.warnings().is_empty());
+
+        fs::write(root.join("config/config.vdf"), config(&mapping("322170", "proton_9"))).unwrap();
+        let info = CompatToolInfo::read(&root, "322170", &tools, None);
+        assert_eq!(info.summary("GD"), "GD uses proton_9 (not installed)");
+        assert_eq!(info.warnings(), ["Steam is set to use proton_9, which isn't installed"]);
+
+        fs::write(root.join("config/config.vdf"), config("")).unwrap();
+        assert_eq!(CompatToolInfo::read(&root, "322170", &tools, None).summary("GD"), "GD uses Steam's default Proton");
+    }
+}
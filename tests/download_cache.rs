@@ -0,0 +1,59 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use geode_cli_installer::utils::download_cache::DownloadCache;
+    use std::fs;
+
+    #[test]
+    fn test_store_lookup_and_prune() {
+        let root = common::temp_dir("cache-prune");
+        let cache = DownloadCache::at(root.join("cache")).with_max_size(25);
+
+        for (tag, content) in [("v4.0.0", [b'a'; 10]), ("v4.1.0", [b'b'; 10])] {
+            let download = root.join("download.zip");
+            fs::write(&download, content).unwrap();
+            let stored = cache.store(tag, &download).unwrap();
+            assert!(stored.starts_with(cache.root()));
+            assert!(!download.exists());
+        }
+
+        assert!(cache.lookup("v4.0.0").unwrap().is_some());
+        assert!(cache.lookup("v9.9.9").unwrap().is_none());
+
+        // v4.1.0 is now the least recently used and has to make room
+        let download = root.join("download.zip");
+        fs::write(&download, [b'c'; 10]).unwrap();
+        cache.store("v4.2.0", &download).unwrap();
+
+        let tags: Vec<String> = cache.entries().unwrap().into_iter().map(|entry| entry.tag).collect();
+        assert_eq!(tags, ["v4.0.0", "v4.2.0"]);
+        assert_eq!(cache.total_size().unwrap(), 20);
+
+        // Unfinished downloads live next to the archives and are cleaned with them
+        let partial = cache.partial_path("geode-v4.3.0-win.zip").unwrap();
+        assert_eq!(partial, cache.root().join("geode-v4.3.0-win.zip.part"));
+        fs::write(&partial, [b'd'; 5]).unwrap();
+
+        assert_eq!(cache.clean().unwrap(), 25);
+        assert!(cache.entries().unwrap().is_empty());
+        assert!(!partial.exists());
+    }
+
+    #[test]
+    fn test_damaged_entry_is_dropped() {
+        let root = common::temp_dir("cache-damaged");
+        let cache = DownloadCache::at(root.join("cache"));
+
+        let download = root.join("download.zip");
+        fs::write(&download, b"archive").unwrap();
+        let stored = cache.store("v4.2.0", &download).unwrap();
+
+        fs::write(&stored, b"truncated").unwrap();
+
+        assert!(cache.lookup("v4.2.0").unwrap().is_none());
+        assert!(cache.entries().unwrap().is_empty());
+        assert!(!stored.exists());
+    }
+}
@@ -0,0 +1,211 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use geode_cli_installer::errors::InstallerError;
+    use geode_cli_installer::utils::downloader::{Downloader, RetryPolicy};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    type Requests = Arc<Mutex<Vec<Option<u64>>>>;
+
+    /// Minimal HTTP server: `respond` gets the request number and the `Range` start, if any
+    fn serve(respond: impl Fn(usize, Option<u64>, &mut TcpStream) + Send + 'static) -> (String, Requests) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/geode.zip", listener.local_addr().unwrap());
+        let requests: Requests = Arc::default();
+
+        let seen = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let range = read_range_header(&stream);
+
+                let index = {
+                    let mut seen = seen.lock().unwrap();
+                    seen.push(range);
+                    seen.len() - 1
+                };
+                respond(index, range, &mut stream);
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn read_range_header(stream: &TcpStream) -> Option<u64> {
+        let mut range = None;
+        for line in BufReader::new(stream).lines() {
+            let line = line.unwrap();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                range = value.trim_end_matches('-').parse().ok();
+            }
+        }
+        range
+    }
+
+    fn body() -> Vec<u8> {
+        (0..64 * 1024).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn downloader() -> Downloader {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+
+        Downloader::new(client, RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(50),
+        })
+    }
+
+    fn output(name: &str) -> PathBuf {
+        common::temp_dir(&format!("download-{}", name)).join("geode.zip")
+    }
+
+    #[test]
+    fn test_resumes_after_dropped_connection() {
+        let (url, requests) = serve(|index, range, stream| {
+            let body = body();
+            match (index, range) {
+                (0, None) => {
+                    write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len()).unwrap();
+                    stream.write_all(&body[..body.len() / 2]).unwrap();
+                    // Dropping the stream here cuts the transfer short
+                }
+                (_, Some(start)) => {
+                    let start = start as usize;
+                    write!(
+                        stream,
+                        "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                        body.len() - start, start, body.len() - 1, body.len()
+                    ).unwrap();
+                    stream.write_all(&body[start..]).unwrap();
+                }
+                _ => panic!("unexpected request without Range"),
+            }
+        });
+
+        let path = output("resume");
+        downloader().download(&url, &path).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), body());
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].is_some_and(|start| start > 0));
+    }
+
+    #[test]
+    fn test_restarts_when_resumed_at_wrong_offset() {
+        let (url, requests) = serve(|index, range, stream| {
+            let body = body();
+            match (index, range) {
+                (0, None) => {
+                    write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len()).unwrap();
+                    stream.write_all(&body[..body.len() / 2]).unwrap();
+                }
+                (1, Some(_)) => {
+                    // Ignores the requested start and sends the last 100 bytes
+                    let start = body.len() - 100;
+                    write!(
+                        stream,
+                        "HTTP/1.1 206 Partial Content\r\nContent-Length: 100\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                        start, body.len() - 1, body.len()
+                    ).unwrap();
+                    stream.write_all(&body[start..]).unwrap();
+                }
+                (_, None) => {
+                    write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len()).unwrap();
+                    stream.write_all(&body).unwrap();
+                }
+                _ => panic!("unexpected request"),
+            }
+        });
+
+        let path = output("wrong-offset");
+        downloader().download(&url, &path).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), body());
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_retries_server_errors() {
+        let (url, requests) = serve(|index, _, stream| {
+            let body = body();
+            match index {
+                0 => write!(stream, "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap(),
+                1 => write!(stream, "HTTP/1.1 429 Too Many Requests\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap(),
+                _ => {
+                    write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len()).unwrap();
+                    stream.write_all(&body).unwrap();
+                }
+            }
+        });
+
+        let path = output("retry");
+        downloader().download(&url, &path).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), body());
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_gives_up_on_client_errors() {
+        let (url, requests) = serve(|_, _, stream| {
+            write!(stream, "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+        });
+
+        let result = downloader().download(&url, &output("missing"));
+
+        assert!(result.is_err());
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_local_write_errors_are_not_retried() {
+        let (url, requests) = serve(|_, _, stream| {
+            let body = body();
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len()).unwrap();
+            // The client hangs up as soon as it can't write
+            let _ = stream.write_all(&body);
+        });
+
+        // Every write to /dev/full fails with ENOSPC, like a full disk
+        let result = downloader().download(&url, Path::new("/dev/full"));
+
+        assert!(matches!(result, Err(InstallerError::Io(_))), "{:?}", result);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_continues_partial_file_from_earlier_run() {
+        let (url, requests) = serve(|_, range, stream| {
+            let body = body();
+            let start = range.expect("expected a Range request") as usize;
+            write!(
+                stream,
+                "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                body.len() - start, start, body.len() - 1, body.len()
+            ).unwrap();
+            stream.write_all(&body[start..]).unwrap();
+        });
+
+        let path = output("partial");
+        std::fs::write(&path, &body()[..1000]).unwrap();
+        downloader().download(&url, &path).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), body());
+        assert_eq!(*requests.lock().unwrap(), [Some(1000)]);
+    }
+}
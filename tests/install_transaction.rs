@@ -0,0 +1,53 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use geode_cli_installer::utils::install_transaction::InstallTransaction;
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn test_next_run_recovers_from_killed_run() {
+        let game_dir = common::temp_dir("transaction-killed");
+        fs::write(game_dir.join("Geode.dll"), b"old loader").unwrap();
+        fs::write(game_dir.join("GeodeUpdater.exe"), b"old updater").unwrap();
+
+        let mut transaction = InstallTransaction::begin(&game_dir).unwrap();
+        let staging = transaction.staging_dir();
+        fs::create_dir_all(staging.join("resources")).unwrap();
+        fs::write(staging.join("Geode.dll"), b"new loader").unwrap();
+        fs::write(staging.join("resources/icon.png"), b"icon").unwrap();
+        transaction.place(Path::new("Geode.dll")).unwrap();
+        transaction.place(Path::new("resources/icon.png")).unwrap();
+        transaction.remove(Path::new("GeodeUpdater.exe")).unwrap();
+        // Killed here: neither committed nor rolled back
+        drop(transaction);
+        assert!(!game_dir.join("GeodeUpdater.exe").exists());
+
+        let transaction = InstallTransaction::begin(&game_dir).unwrap();
+        assert_eq!(fs::read(game_dir.join("Geode.dll")).unwrap(), b"old loader");
+        assert_eq!(fs::read(game_dir.join("GeodeUpdater.exe")).unwrap(), b"old updater");
+        assert!(!game_dir.join("resources/icon.png").exists());
+
+        transaction.commit().unwrap();
+        let mut names: Vec<_> = fs::read_dir(&game_dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        names.sort();
+        assert_eq!(names, ["Geode.dll", "GeodeUpdater.exe"]);
+    }
+
+    #[test]
+    fn test_committed_changes_are_kept() {
+        let game_dir = common::temp_dir("transaction-committed");
+        fs::write(game_dir.join("Geode.dll"), b"old loader").unwrap();
+
+        let mut transaction = InstallTransaction::begin(&game_dir).unwrap();
+        fs::write(transaction.staging_dir().join("Geode.dll"), b"new loader").unwrap();
+        transaction.place(Path::new("Geode.dll")).unwrap();
+        transaction.commit().unwrap();
+
+        InstallTransaction::begin(&game_dir).unwrap().commit().unwrap();
+        assert_eq!(fs::read(game_dir.join("Geode.dll")).unwrap(), b"new loader");
+        assert_eq!(fs::read_dir(&game_dir).unwrap().count(), 1);
+    }
+}
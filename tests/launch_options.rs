@@ -0,0 +1,61 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use geode_cli_installer::utils::launch_options::{self, LocalConfig};
+    use std::fs;
+
+    const LOCALCONFIG: &str = "\"UserLocalConfigStore\"\n{\n\t\"Software\"\n\t{\n\t\t\"Valve\"\n\t\t{\n\t\t\t\"Steam\"\n\t\t\t{\n\
+\t\t\t\t\"apps\"\n\t\t\t\t{\n\t\t\t\t\t\"322170\"\n\t\t\t\t\t{\n\t\t\t\t\t\t\"LastPlayed\"\t\t\"1700000000\"\n\
+\t\t\t\t\t\t\"LaunchOptions\"\t\t\"PROTON_LOG=1 %command% -windowed\"\n\t\t\t\t\t}\n\t\t\t\t}\n\t\t\t}\n\t\t}\n\t}\n}\n";
+
+    #[test]
+    fn test_merges_dll_override_into_launch_options() {
+        let cases = [
+            ("", "WINEDLLOVERRIDES=\"xinput1_4=n,b\" %command%"),
+            ("-windowed", "WINEDLLOVERRIDES=\"xinput1_4=n,b\" %command% -windowed"),
+            ("gamemoderun %command%", "WINEDLLOVERRIDES=\"xinput1_4=n,b\" gamemoderun %command%"),
+            ("WINEDLLOVERRIDES=\"d3d9=n\" %command%", "WINEDLLOVERRIDES=\"d3d9=n;xinput1_4=n,b\" %command%"),
+            ("WINEDLLOVERRIDES=xinput1_4,d3d9=b %command%", "WINEDLLOVERRIDES=\"d3d9=b;xinput1_4=n,b\" %command%"),
+        ];
+
+        for (options, expected) in cases {
+            let merged = launch_options::with_dll_override(options, "xinput1_4", "n,b");
+            assert_eq!(merged, expected, "merging into {:?}", options);
+            assert_eq!(launch_options::dll_override(&merged, "xinput1_4").as_deref(), Some("n,b"));
+        }
+
+        assert_eq!(launch_options::without_dll_override("WINEDLLOVERRIDES=\"xinput1_4=n,b\" %command%", "xinput1_4"), "");
+        assert_eq!(
+            launch_options::without_dll_override("WINEDLLOVERRIDES=\"xinput1_4=n,b\" gamemoderun %command% -windowed", "xinput1_4"),
+            "gamemoderun %command% -windowed"
+        );
+        // Arguments after %command% go to the game, not to Wine
+        assert_eq!(launch_options::dll_override("%command% WINEDLLOVERRIDES=xinput1_4=n", "xinput1_4"), None);
+    }
+
+    #[test]
+    fn test_local_config_round_trip() {
+        let path = common::temp_dir("localconfig").join("localconfig.vdf");
+        fs::write(&path, LOCALCONFIG).unwrap();
+
+        let mut config = LocalConfig::load(&path).unwrap();
+        assert_eq!(config.launch_options("322170"), Some("PROTON_LOG=1 %command% -windowed"));
+
+        let merged = launch_options::with_dll_override(config.launch_options("322170").unwrap(), "xinput1_4", "n,b");
+        config.set_launch_options("322170", &merged);
+        config.save().unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("\"LaunchOptions\"\t\t\"WINEDLLOVERRIDES=\\\"xinput1_4=n,b\\\" PROTON_LOG=1 %command% -windowed\""));
+        assert!(saved.contains("\"LastPlayed\"\t\t\"1700000000\""));
+
+        let mut config = LocalConfig::load(&path).unwrap();
+        config.set_launch_options("322170", "PROTON_LOG=1 %command% -windowed");
+        config.save().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), LOCALCONFIG);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    use geode_cli_installer::utils::geode_installer::{self, LoaderRelease};
+    use serde_json::{Value, json};
+
+    fn releases() -> Value {
+        json!([
+            { "tag": "v4.3.0-beta.1", "prerelease": true, "created_at": "2025-03-01T12:00:00Z", "gd": { "win": "2.2074" } },
+            { "tag": "v4.2.0", "prerelease": false, "created_at": "2025-02-01T12:00:00Z", "gd": { "win": "2.2074", "mac": "2.2074" } },
+            { "tag": "v4.1.0", "gd": {} },
+            { "prerelease": false },
+        ])
+    }
+
+    fn tags(releases: &[LoaderRelease]) -> Vec<&str> {
+        releases.iter().map(|release| release.tag.as_str()).collect()
+    }
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(geode_installer::normalize_tag("4.2.0"), "v4.2.0");
+        assert_eq!(geode_installer::normalize_tag("v4.2.0"), "v4.2.0");
+        assert_eq!(geode_installer::normalize_tag("  4.2.0\n"), "v4.2.0");
+        assert_eq!(geode_installer::normalize_tag(" v4.2.0-beta.1 "), "v4.2.0-beta.1");
+    }
+
+    #[test]
+    fn test_parse_bare_and_paginated_listings() {
+        let bare = releases();
+        let paginated = json!({ "data": releases(), "count": 4 });
+
+        for payload in [&bare, &paginated] {
+            let all = geode_installer::parse_releases(payload, true).unwrap();
+            // Entries without a tag are skipped
+            assert_eq!(tags(&all), ["v4.3.0-beta.1", "v4.2.0", "v4.1.0"]);
+            assert!(all[0].prerelease);
+            assert_eq!(all[1].created_at.as_deref(), Some("2025-02-01T12:00:00Z"));
+            assert_eq!(all[1].gd_version.as_deref(), Some("2.2074"));
+            assert!(!all[2].prerelease);
+            assert_eq!(all[2].gd_version, None);
+
+            let stable = geode_installer::parse_releases(payload, false).unwrap();
+            assert_eq!(tags(&stable), ["v4.2.0", "v4.1.0"]);
+        }
+    }
+
+    #[test]
+    fn test_rejects_payload_without_listing() {
+        assert!(geode_installer::parse_releases(&json!({ "tag": "v4.2.0" }), true).is_err());
+        assert!(geode_installer::parse_releases(&Value::Null, true).is_err());
+    }
+}
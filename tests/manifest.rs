@@ -0,0 +1,47 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use geode_cli_installer::utils::install_manifest::{FileProblem, InstallManifest, ManifestFile};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_manifest_roundtrip_and_verify() {
+        let game_dir = common::temp_dir("manifest");
+
+        fs::write(game_dir.join("Geode.dll"), b"loader").unwrap();
+        fs::write(game_dir.join("XInput1_4.dll"), b"proxy").unwrap();
+
+        let manifest = InstallManifest {
+            tag: "v4.2.0".into(),
+            installed_at: 1_700_000_000,
+            game_dir: game_dir.clone(),
+            prefix: PathBuf::from("/tmp/pfx"),
+            files: vec![
+                ManifestFile::record(&game_dir, Path::new("Geode.dll"), 0).unwrap(),
+                ManifestFile::record(&game_dir, Path::new("XInput1_4.dll"), 0).unwrap(),
+            ],
+            registry: Vec::new(),
+            launch_options: Vec::new(),
+        };
+        manifest.save().unwrap();
+
+        let loaded = InstallManifest::load(&game_dir).unwrap().unwrap();
+        assert_eq!(loaded.tag, "v4.2.0");
+        assert_eq!(loaded.files[0].size, 6);
+        assert!(loaded.verify().unwrap().is_empty());
+
+        fs::write(game_dir.join("Geode.dll"), b"patchd").unwrap();
+        fs::remove_file(game_dir.join("XInput1_4.dll")).unwrap();
+
+        assert_eq!(
+            loaded.verify().unwrap(),
+            vec![
+                FileProblem::HashMismatch(PathBuf::from("Geode.dll")),
+                FileProblem::Missing(PathBuf::from("XInput1_4.dll")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,258 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{USER_REG, setup, write_archive};
+    use geode_cli_installer::errors::InstallerError;
+    use geode_cli_installer::utils::geode_installer::{GeodeInstaller, InstallOptions, OverrideScope, UpdateSummary};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn installer_for(archive: &Path) -> GeodeInstaller {
+        GeodeInstaller::with_options(InstallOptions {
+            archive: Some(archive.to_path_buf()),
+            ..Default::default()
+        }).unwrap()
+    }
+
+    #[test]
+    fn test_install_and_uninstall_from_local_archive() {
+        let (root, prefix, game_dir) = setup("offline-roundtrip");
+        let archive = root.join("geode-v4.2.0-win.zip");
+        write_archive(&archive, &[
+            ("Geode.dll", b"loader"),
+            ("XInput1_4.dll", b"proxy"),
+            ("GeodeUpdater.exe", b"updater"),
+        ]);
+
+        let installer = installer_for(&archive);
+        installer.install_to_wine(&prefix, &game_dir).unwrap();
+
+        assert!(archive.exists());
+        assert_eq!(fs::read(game_dir.join("Geode.dll")).unwrap(), b"loader");
+        assert!(fs::read_to_string(prefix.join("user.reg")).unwrap()
+            .contains("\"xinput1_4\"=\"native,builtin\""));
+
+        let manifest = installer.manifest(&game_dir).unwrap().unwrap();
+        assert_eq!(manifest.tag, "v4.2.0");
+        assert_eq!(manifest.files.len(), 3);
+        assert!(installer.verify(&game_dir).unwrap().is_empty());
+
+        installer.uninstall_from_wine(&prefix, &game_dir, false).unwrap();
+        assert_eq!(fs::read_to_string(prefix.join("user.reg")).unwrap(), USER_REG);
+        assert_eq!(fs::read_dir(&game_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_rejects_archive_without_loader() {
+        let (root, prefix, game_dir) = setup("offline-invalid");
+        let archive = root.join("geode-v4.2.0-win.zip");
+        write_archive(&archive, &[("readme.txt", b"not geode")]);
+
+        let result = installer_for(&archive).install_to_wine(&prefix, &game_dir);
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(prefix.join("user.reg")).unwrap(), USER_REG);
+    }
+
+    #[test]
+    fn test_failed_install_rolls_back() {
+        let (root, prefix, game_dir) = setup("offline-rollback");
+        let archive = root.join("geode-v4.2.0-win.zip");
+        write_archive(&archive, &[
+            ("Geode.dll", b"loader"),
+            ("XInput1_4.dll", b"proxy"),
+            ("resources/geode.png", b"icon"),
+        ]);
+        fs::write(game_dir.join("Geode.dll"), b"old loader").unwrap();
+        // Patching the registry is the last step, so everything before it must be undone
+        fs::remove_file(prefix.join("user.reg")).unwrap();
+
+        let result = installer_for(&archive).install_to_wine(&prefix, &game_dir);
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(game_dir.join("Geode.dll")).unwrap(), b"old loader");
+        assert_eq!(fs::read_dir(&game_dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_custom_prefix_gets_per_app_override() {
+        let (root, prefix, game_dir) = setup("offline-scope");
+        let archive = root.join("geode-v4.2.0-win.zip");
+        write_archive(&archive, &[("Geode.dll", b"loader"), ("XInput1_4.dll", b"proxy")]);
+
+        let installer = installer_for(&archive);
+        installer.install_to_wine(&prefix, &game_dir).unwrap();
+
+        let user_reg = fs::read_to_string(prefix.join("user.reg")).unwrap();
+        assert!(user_reg.contains("[Software\\\\Wine\\\\AppDefaults\\\\GeometryDash.exe\\\\DllOverrides]"));
+        assert!(!user_reg.contains("[Software\\\\Wine\\\\DllOverrides]"));
+
+        let status = installer.status(&prefix, &game_dir).unwrap();
+        let dll_override = status.dll_override.unwrap();
+        assert_eq!(dll_override.scope, OverrideScope::App);
+        assert_eq!(dll_override.value, "native,builtin");
+
+        installer.uninstall_from_wine(&prefix, &game_dir, false).unwrap();
+        assert_eq!(fs::read_to_string(prefix.join("user.reg")).unwrap(), USER_REG);
+    }
+
+    #[test]
+    fn test_rewrites_override_that_blocks_geode() {
+        let (root, prefix, game_dir) = setup("offline-conflict");
+        let original = format!("{}\n[Software\\\\Wine\\\\DllOverrides] 1700000001\n\"xinput1_4\"=\"builtin\"\n", USER_REG);
+        fs::write(prefix.join("user.reg"), &original).unwrap();
+        let archive = root.join("geode-v4.2.0-win.zip");
+        write_archive(&archive, &[("Geode.dll", b"loader"), ("XInput1_4.dll", b"proxy")]);
+
+        let installer = installer_for(&archive);
+        let conflict = installer.conflicting_dll_override(&prefix).unwrap().unwrap();
+        assert_eq!(conflict.scope, OverrideScope::Prefix);
+        assert_eq!(conflict.value, "builtin");
+
+        installer.install_to_wine(&prefix, &game_dir).unwrap();
+        assert!(installer.conflicting_dll_override(&prefix).unwrap().is_none());
+        assert!(installer.status(&prefix, &game_dir).unwrap().dll_override.unwrap().loads_native());
+
+        installer.uninstall_from_wine(&prefix, &game_dir, false).unwrap();
+        assert_eq!(fs::read_to_string(prefix.join("user.reg")).unwrap(), original);
+    }
+
+    #[test]
+    fn test_launch_options_mode_leaves_registry_alone() {
+        let (root, prefix, game_dir) = setup("offline-launch-options");
+        let config = root.join("steam/userdata/12345/config/localconfig.vdf");
+        fs::create_dir_all(config.parent().unwrap()).unwrap();
+        let original = "\"UserLocalConfigStore\"\n{\n\t\"Software\"\n\t{\n\t\t\"Valve\"\n\t\t{\n\t\t\t\"Steam\"\n\t\t\t{\n\
+\t\t\t\t\"apps\"\n\t\t\t\t{\n\t\t\t\t\t\"322170\"\n\t\t\t\t\t{\n\t\t\t\t\t\t\"LaunchOptions\"\t\t\"-windowed\"\n\
+\t\t\t\t\t}\n\t\t\t\t}\n\t\t\t}\n\t\t}\n\t}\n}\n";
+        fs::write(&config, original).unwrap();
+        let archive = root.join("geode-v4.2.0-win.zip");
+        write_archive(&archive, &[("Geode.dll", b"loader"), ("XInput1_4.dll", b"proxy")]);
+
+        // Whether Steam runs on this machine doesn't matter; only the fake /proc is looked at
+        let proc_root = root.join("proc");
+        fs::create_dir_all(proc_root.join("4242")).unwrap();
+        fs::write(proc_root.join("4242/comm"), "steam\n").unwrap();
+        let installer = GeodeInstaller::with_options(InstallOptions {
+            archive: Some(archive.clone()),
+            launch_options: true,
+            steam_root: Some(root.join("steam")),
+            proc_root: Some(proc_root.clone()),
+            ..Default::default()
+        }).unwrap();
+        assert!(matches!(installer.install_to_wine(&prefix, &game_dir), Err(InstallerError::SteamRunning(_))));
+        assert_eq!(fs::read_to_string(&config).unwrap(), original);
+
+        fs::remove_dir_all(proc_root.join("4242")).unwrap();
+        installer.install_to_wine(&prefix, &game_dir).unwrap();
+
+        assert_eq!(fs::read_to_string(prefix.join("user.reg")).unwrap(), USER_REG);
+        assert!(fs::read_to_string(&config).unwrap()
+            .contains("\"LaunchOptions\"\t\t\"WINEDLLOVERRIDES=\\\"xinput1_4=n,b\\\" %command% -windowed\""));
+        let status = installer.status(&prefix, &game_dir).unwrap();
+        assert_eq!(status.launch_option_override.as_deref(), Some("n,b"));
+
+        installer.uninstall_from_wine(&prefix, &game_dir, false).unwrap();
+        assert_eq!(fs::read_to_string(&config).unwrap(), original);
+    }
+
+    #[test]
+    fn test_update_keeps_user_data_and_removes_stale_files() {
+        let (root, prefix, game_dir) = setup("offline-update");
+        let old = root.join("geode-v4.2.0-win.zip");
+        write_archive(&old, &[
+            ("Geode.dll", b"loader"),
+            ("XInput1_4.dll", b"proxy"),
+            ("GeodeUpdater.exe", b"updater"),
+            ("resources/old.png", b"old icon"),
+        ]);
+        installer_for(&old).install_to_wine(&prefix, &game_dir).unwrap();
+        fs::create_dir_all(game_dir.join("geode/mods")).unwrap();
+        fs::create_dir_all(game_dir.join("geode/config")).unwrap();
+        fs::write(game_dir.join("geode/mods/my.mod.geode"), b"mod").unwrap();
+        fs::write(game_dir.join("geode/config/settings.json"), b"{}").unwrap();
+
+        let new = root.join("geode-v4.3.0-win.zip");
+        write_archive(&new, &[
+            ("Geode.dll", b"new loader"),
+            ("XInput1_4.dll", b"proxy"),
+            ("GeodeUpdater.exe", b"updater"),
+            ("resources/new.png", b"new icon"),
+            // Releases never ship these, but an update must not clobber them if one did
+            ("geode/mods/my.mod.geode", b"bundled"),
+            ("geode/config/settings.json", b"defaults"),
+        ]);
+        let installer = installer_for(&new);
+        let check = installer.check_update(&game_dir).unwrap();
+        assert_eq!(check.installed.as_deref(), Some("v4.2.0"));
+        assert_eq!(check.available, "v4.3.0");
+        assert!(!check.is_up_to_date());
+
+        // Planning shows the changes without touching the installation
+        let plan = installer.plan_update(&game_dir, &check).unwrap();
+        assert_eq!(plan.summary, UpdateSummary {
+            added: vec![PathBuf::from("resources/new.png")],
+            changed: vec![PathBuf::from("Geode.dll")],
+            removed: vec![PathBuf::from("resources/old.png")],
+        });
+        assert_eq!(fs::read(game_dir.join("Geode.dll")).unwrap(), b"loader");
+
+        installer.apply_update(&prefix, &game_dir, &plan).unwrap();
+        assert_eq!(fs::read(game_dir.join("Geode.dll")).unwrap(), b"new loader");
+        assert!(game_dir.join("resources/new.png").exists());
+        assert!(!game_dir.join("resources/old.png").exists());
+        assert_eq!(fs::read(game_dir.join("geode/mods/my.mod.geode")).unwrap(), b"mod");
+        assert_eq!(fs::read(game_dir.join("geode/config/settings.json")).unwrap(), b"{}");
+        assert!(installer.check_update(&game_dir).unwrap().is_up_to_date());
+        assert!(installer.verify(&game_dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_update_without_manifest_reinstalls() {
+        let (root, prefix, game_dir) = setup("offline-update-unknown");
+        fs::write(game_dir.join("Geode.dll"), b"loader").unwrap();
+        fs::write(game_dir.join("XInput1_4.dll"), b"old proxy").unwrap();
+        let archive = root.join("geode-v4.2.0-win.zip");
+        write_archive(&archive, &[("Geode.dll", b"loader"), ("XInput1_4.dll", b"proxy"), ("GeodeUpdater.exe", b"updater")]);
+
+        let installer = installer_for(&archive);
+        let check = installer.check_update(&game_dir).unwrap();
+        assert_eq!(check.installed, None);
+        assert!(!check.is_up_to_date());
+
+        // Unknown earlier files are compared with what is on disk and never reported as removed
+        let plan = installer.plan_update(&game_dir, &check).unwrap();
+        assert_eq!(plan.summary, UpdateSummary {
+            added: vec![PathBuf::from("GeodeUpdater.exe")],
+            changed: vec![PathBuf::from("XInput1_4.dll")],
+            removed: Vec::new(),
+        });
+
+        installer.apply_update(&prefix, &game_dir, &plan).unwrap();
+        assert_eq!(installer.manifest(&game_dir).unwrap().unwrap().files.len(), 3);
+        assert!(installer.check_update(&game_dir).unwrap().is_up_to_date());
+    }
+
+    #[test]
+    fn test_reinstall_keeps_original_registry_values() {
+        let (root, prefix, game_dir) = setup("offline-reinstall");
+        let archive = root.join("geode-v4.2.0-win.zip");
+        write_archive(&archive, &[("Geode.dll", b"loader"), ("XInput1_4.dll", b"proxy")]);
+        let installer = installer_for(&archive);
+        installer.install_to_wine(&prefix, &game_dir).unwrap();
+
+        // Something sets the override back to builtin, and Geode is installed again
+        let user_reg = fs::read_to_string(prefix.join("user.reg")).unwrap();
+        fs::write(prefix.join("user.reg"), user_reg.replace("\"native,builtin\"", "\"builtin\"")).unwrap();
+        installer.install_to_wine(&prefix, &game_dir).unwrap();
+
+        let registry = installer.manifest(&game_dir).unwrap().unwrap().registry;
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry[0].previous, None);
+        assert!(registry[0].created_section);
+
+        installer.uninstall_from_wine(&prefix, &game_dir, false).unwrap();
+        assert_eq!(fs::read_to_string(prefix.join("user.reg")).unwrap(), USER_REG);
+    }
+}
@@ -0,0 +1,121 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{setup, write_archive, write_script};
+    use geode_cli_installer::utils::geode_installer::{GeodeInstaller, InstallOptions, OverrideScope};
+    use geode_cli_installer::utils::registry_backend::RegistryBackendKind;
+    use std::fs;
+    use std::process::Command;
+
+    /// Keeps `KEY<TAB>NAME<TAB>DATA` lines in `$WINEPREFIX/stub.reg`; `*` marks that a key exists
+    const STUB_WINE: &str = r#"#!/bin/sh
+db="$WINEPREFIX/stub.reg"
+touch "$db"
+# Like wine, leave a wineserver running for the prefix until `wineserver -w`
+server="$(dirname "$0")/server/wineserver"
+if [ -x "$server" ] && ! kill -0 "$(cat "$WINEPREFIX/server.pid" 2>/dev/null)" 2>/dev/null; then
+    "$server" </dev/null >/dev/null 2>&1 &
+    echo $! > "$WINEPREFIX/server.pid"
+fi
+echo "$*" >> "$WINEPREFIX/calls.log"
+[ "$1" = reg ] || exit 2
+op=$2; K=$3; N=""; D=""; shift 3
+while [ $# -gt 0 ]; do
+    case $1 in
+        /v) N=$2; shift 2 ;;
+        /d) D=$2; shift 2 ;;
+        /t) shift 2 ;;
+        *) shift ;;
+    esac
+done
+export K N D
+keep() { awk -F '\t' "$1" "$db" > "$db.new"; mv "$db.new" "$db"; }
+case $op in
+    query)
+        awk -F '\t' '$1 == ENVIRON["K"] { found = 1 } END { exit !found }' "$db" || exit 1
+        echo "$K"
+        awk -F '\t' '$1 == ENVIRON["K"] && $2 != "*" { printf "    %s    REG_SZ    %s\n", $2, $3 }' "$db"
+        ;;
+    add)
+        keep '!($1 == ENVIRON["K"] && $2 == ENVIRON["N"])'
+        printf '%s\t*\t\n%s\t%s\t%s\n' "$K" "$K" "$N" "$D" >> "$db"
+        ;;
+    delete)
+        if [ -n "$N" ]; then keep '!($1 == ENVIRON["K"] && $2 == ENVIRON["N"])'; else keep '$1 != ENVIRON["K"]'; fi
+        ;;
+esac
+"#;
+
+    /// Stops the wineserver the stub `wine` left running
+    const STUB_WINESERVER: &str = r#"#!/bin/sh
+echo "wineserver $*" >> "$WINEPREFIX/calls.log"
+[ -f "$WINEPREFIX/server.pid" ] && kill "$(cat "$WINEPREFIX/server.pid")" 2>/dev/null
+rm -f "$WINEPREFIX/server.pid"
+exit 0
+"#;
+
+    #[test]
+    fn test_install_through_wine_reg() {
+        let (root, prefix, game_dir) = setup("registry-backend");
+        let bin = root.join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        write_script(&bin.join("wine"), STUB_WINE);
+        write_script(&bin.join("wineserver"), STUB_WINESERVER);
+
+        let archive = root.join("geode-v4.2.0-win.zip");
+        write_archive(&archive, &[("Geode.dll", b"loader"), ("XInput1_4.dll", b"proxy!")]);
+
+        let installer = GeodeInstaller::with_options(InstallOptions {
+            archive: Some(archive),
+            registry_backend: RegistryBackendKind::Wine,
+            wine_binary: Some(bin.join("wine")),
+            ..Default::default()
+        }).unwrap();
+        installer.install_to_wine(&prefix, &game_dir).unwrap();
+
+        let calls = fs::read_to_string(prefix.join("calls.log")).unwrap();
+        assert!(calls.contains(
+            "reg add HKEY_CURRENT_USER\\Software\\Wine\\AppDefaults\\GeometryDash.exe\\DllOverrides \
+             /v xinput1_4 /t REG_SZ /d native,builtin /f"
+        ));
+        assert!(calls.ends_with("wineserver -w\n"));
+
+        let dll_override = installer.status(&prefix, &game_dir).unwrap().dll_override.unwrap();
+        assert_eq!(dll_override.scope, OverrideScope::App);
+        assert_eq!(dll_override.value, "native,builtin");
+
+        installer.uninstall_from_wine(&prefix, &game_dir, false).unwrap();
+        assert!(installer.status(&prefix, &game_dir).unwrap().dll_override.is_none());
+        assert_eq!(fs::read_to_string(prefix.join("stub.reg")).unwrap(), "");
+    }
+
+    #[test]
+    fn test_cli_install_with_wine_backend_leaves_prefix_idle() {
+        let (root, prefix, game_dir) = setup("registry-backend-cli");
+        let bin = root.join("bin");
+        fs::create_dir_all(bin.join("server")).unwrap();
+        write_script(&bin.join("wine"), STUB_WINE);
+        write_script(&bin.join("wineserver"), STUB_WINESERVER);
+        write_script(&bin.join("server/wineserver"), "#!/bin/sh\nsleep 30\n");
+
+        let archive = root.join("geode-v4.2.0-win.zip");
+        write_archive(&archive, &[("Geode.dll", b"loader"), ("XInput1_4.dll", b"proxy!")]);
+
+        // The override check before installing reads through `wine reg`, which starts a wineserver
+        let output = Command::new(env!("CARGO_BIN_EXE_geode-cli-installer"))
+            .args(["install", "--wine", "--yes", "--registry-backend", "wine"])
+            .arg("--prefix").arg(&prefix)
+            .arg("--game-dir").arg(&game_dir)
+            .arg("--from-zip").arg(&archive)
+            .arg("--wine-binary").arg(bin.join("wine"))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+
+        let calls = fs::read_to_string(prefix.join("calls.log")).unwrap();
+        assert!(calls.contains("reg query HKEY_CURRENT_USER\\Software\\Wine\\AppDefaults\\GeometryDash.exe\\DllOverrides"));
+        assert!(calls.contains("/v xinput1_4 /t REG_SZ /d native,builtin /f"));
+        assert!(!prefix.join("server.pid").exists());
+    }
+}
@@ -0,0 +1,76 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use geode_cli_installer::errors::InstallerError;
+    use geode_cli_installer::utils::geode_installer::GeodeInstaller;
+    use geode_cli_installer::utils::registry_backup::RegistryBackups;
+    use std::fs;
+    use std::process::{Command, Stdio};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_backups_are_pruned_and_restorable() {
+        let prefix = common::temp_dir("registry-backup");
+        let backups = RegistryBackups::in_prefix(&prefix).with_keep(2);
+
+        for version in ["first", "second", "third"] {
+            fs::write(prefix.join("user.reg"), version).unwrap();
+            backups.create("user.reg").unwrap();
+        }
+
+        let list = backups.list().unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(fs::read_to_string(&list[0].path).unwrap(), "third");
+        assert_eq!(fs::read_to_string(&list[1].path).unwrap(), "second");
+
+        fs::write(prefix.join("user.reg"), "broken").unwrap();
+        backups.restore(&list[1]).unwrap();
+
+        assert_eq!(fs::read_to_string(prefix.join("user.reg")).unwrap(), "second");
+        // The state before the restore is kept too
+        let newest = &backups.list().unwrap()[0];
+        assert_eq!(fs::read_to_string(&newest.path).unwrap(), "broken");
+
+        // Keeping none would delete the backup just taken
+        let latest = RegistryBackups::in_prefix(&prefix).with_keep(0).create("user.reg").unwrap();
+        assert!(latest.path.exists());
+        assert_eq!(backups.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_restore_refuses_running_prefix() {
+        let root = common::temp_dir("registry-restore");
+        let prefix = root.join("pfx");
+        fs::create_dir_all(&prefix).unwrap();
+        fs::write(prefix.join("user.reg"), "backed up").unwrap();
+        let backups = RegistryBackups::in_prefix(&prefix);
+        let backup = backups.create("user.reg").unwrap();
+        fs::write(prefix.join("user.reg"), "current").unwrap();
+
+        // A stand-in wineserver serving the prefix
+        let server = root.join("wineserver");
+        common::write_script(&server, "#!/bin/sh\nsleep 30\n");
+        let mut running = Command::new(&server).env("WINEPREFIX", &prefix).stdout(Stdio::null()).spawn().unwrap();
+        // Until the child has exec'd the script, /proc shows it as a copy of this test binary
+        let comm = format!("/proc/{}/comm", running.id());
+        let started = Instant::now();
+        while fs::read_to_string(&comm).unwrap_or_default().trim_end() != "wineserver" {
+            assert!(started.elapsed() < Duration::from_secs(10), "stub wineserver didn't start");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let installer = GeodeInstaller::new().unwrap();
+        let result = installer.restore_registry_backup(&prefix, &backup);
+        running.kill().unwrap();
+        running.wait().unwrap();
+
+        assert!(matches!(result, Err(InstallerError::PrefixInUse(_))), "{:?}", result);
+        assert_eq!(fs::read_to_string(prefix.join("user.reg")).unwrap(), "current");
+
+        installer.restore_registry_backup(&prefix, &backup).unwrap();
+        assert_eq!(fs::read_to_string(prefix.join("user.reg")).unwrap(), "backed up");
+    }
+}
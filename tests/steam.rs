@@ -1,6 +1,14 @@
+mod common;
+
 #[cfg(test)]
 mod tests {
-    use geode_cli_installer::utils::steam_game_finder::SteamGameFinder;
+    use crate::common;
+    use geode_cli_installer::errors::InstallerError;
+    use geode_cli_installer::utils::geode_installer::{GeodeInstaller, InstallOptions};
+    use geode_cli_installer::utils::steam_game_finder::{SteamGameFinder, SteamInstallation, SteamPackaging, StateFlags};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
 
     #[test]
     fn test_steam_finder() {
@@ -8,4 +16,163 @@ mod tests {
         println!("Steam root: {:?}", finder.steam_root());
         println!("Library folders: {:?}", finder.library_folders());
     }
-}
\ No newline at end of file
+
+    /// A Steam installation whose library list puts GD in `listed_in`, with GD actually
+    /// installed in `installed_in`
+    fn fake_steam(name: &str, listed_in: &str, installed_in: &str) -> PathBuf {
+        let root = common::temp_dir(&format!("steam-{}", name));
+        let steam = root.join("steam");
+        let external = root.join("external");
+
+        let mut libraries = String::from("\"libraryfolders\"\n{\n");
+        for (i, (path, label)) in [(&steam, ""), (&external, "Games")].into_iter().enumerate() {
+            fs::create_dir_all(path.join("steamapps")).unwrap();
+            let apps = if label == listed_in { "\t\t\t\"322170\"\t\t\"216514290\"\n" } else { "" };
+            libraries.push_str(&format!(
+                "\t\"{}\"\n\t{{\n\t\t\"path\"\t\t\"{}\"\n\t\t\"label\"\t\t\"{}\"\n\t\t\"contentid\"\t\t\"4132884245467836163\"\n\
+\t\t\"totalsize\"\t\t\"2000381014016\"\n\t\t\"apps\"\n\t\t{{\n{}\t\t}}\n\t}}\n",
+                i, path.display(), label, apps,
+            ));
+        }
+        libraries.push_str("}\n");
+        fs::write(steam.join("steamapps/libraryfolders.vdf"), libraries).unwrap();
+
+        let library = if installed_in == "Games" { &external } else { &steam };
+        install_gd(library);
+        root
+    }
+
+    fn install_gd(library: &Path) {
+        fs::create_dir_all(library.join("steamapps/common/Geometry Dash")).unwrap();
+        fs::write(
+            library.join("steamapps/appmanifest_322170.acf"),
+            "\"AppState\"\n{\n\t\"appid\"\t\t\"322170\"\n\t\"name\"\t\t\"Geometry Dash\"\n\t\"StateFlags\"\t\t\"4\"\n\t\"installdir\"\t\t\"Geometry Dash\"\n}\n",
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_libraries_locate_game() {
+        let root = fake_steam("listed", "Games", "Games");
+        let finder = SteamGameFinder::at(root.join("steam"));
+
+        let libraries = finder.libraries();
+        assert_eq!(libraries.len(), 2);
+        assert_eq!(libraries[1].path, root.join("external"));
+        assert_eq!(libraries[1].label, "Games");
+        assert_eq!(libraries[1].content_id.as_deref(), Some("4132884245467836163"));
+        assert_eq!(libraries[1].total_size, Some(2000381014016));
+        assert_eq!(libraries[1].apps.as_ref().unwrap().get("322170"), Some(&216514290));
+        assert!(libraries[1].lists("322170"));
+        assert!(!libraries[0].lists("322170"));
+
+        let game = finder.get_game_info("322170").unwrap();
+        assert_eq!(game.library_path, root.join("external/steamapps"));
+        assert_eq!(game.game_path, root.join("external/steamapps/common/Geometry Dash"));
+    }
+
+    #[test]
+    fn test_stale_library_list_falls_back_to_probing() {
+        let root = fake_steam("stale", "Games", "");
+        let finder = SteamGameFinder::at(root.join("steam"));
+
+        let game = finder.get_game_info("322170").unwrap();
+        assert_eq!(game.library_path, root.join("steam/steamapps"));
+        assert!(finder.get_game_info("10").is_none());
+    }
+
+    #[test]
+    fn test_discovers_each_installation_once() {
+        let root = common::temp_dir("steam-roots");
+        let native = root.join("native/Steam");
+        let other = root.join("other/Steam");
+        fs::create_dir_all(native.join("steamapps")).unwrap();
+        fs::create_dir_all(other.join("steamapps")).unwrap();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        std::os::unix::fs::symlink(&native, root.join("link")).unwrap();
+
+        let installations = SteamInstallation::discover_from([
+            root.join("link"),
+            root.join("missing"),
+            root.join("empty"),
+            native.clone(),
+            other.clone(),
+        ]);
+        assert_eq!(installations, [
+            SteamInstallation { root: native.clone(), packaging: SteamPackaging::Native },
+            SteamInstallation { root: other, packaging: SteamPackaging::Native },
+        ]);
+
+        let finder = SteamGameFinder::at(root.join("link"));
+        assert_eq!(finder.steam_root(), Some(&native));
+    }
+
+    #[test]
+    fn test_installed_apps() {
+        let root = fake_steam("apps", "Games", "Games");
+        let external = root.join("external/steamapps");
+        fs::create_dir_all(external.join("compatdata/322170/pfx")).unwrap();
+        fs::write(
+            external.join("appmanifest_504230.acf"),
+            "\"AppState\"\n{\n\t\"appid\"\t\t\"504230\"\n\t\"name\"\t\t\"Celeste\"\n\t\"StateFlags\"\t\t\"4\"\n\
+\t\"installdir\"\t\t\"Celeste\"\n\t\"LastUpdated\"\t\t\"1700000000\"\n\t\"SizeOnDisk\"\t\t\"1200000000\"\n\
+\t\"buildid\"\t\t\"12345\"\n}\n",
+        ).unwrap();
+        // A leftover manifest in another library doesn't list the game twice
+        install_gd(&root.join("steam"));
+
+        let apps = SteamGameFinder::at(root.join("steam")).installed_apps();
+        let ids: Vec<&str> = apps.iter().map(|app| app.app_id.as_str()).collect();
+        assert_eq!(ids, ["504230", "322170"]);
+
+        let celeste = &apps[0];
+        assert_eq!(celeste.name, "Celeste");
+        assert_eq!(celeste.build_id, Some(12345));
+        assert_eq!(celeste.install_dir, external.join("common/Celeste"));
+        assert_eq!(celeste.state_flags, StateFlags(StateFlags::FULLY_INSTALLED));
+        assert_eq!(celeste.size_on_disk, 1200000000);
+        assert_eq!(celeste.last_updated, Some(1700000000));
+        assert_eq!(celeste.proton_prefix, None);
+        assert_eq!(apps[1].library_path, external);
+        assert_eq!(apps[1].proton_prefix, Some(external.join("compatdata/322170/pfx")));
+    }
+
+    #[test]
+    fn test_refuses_game_steam_is_updating() {
+        let root = fake_steam("updating", "", "");
+        let manifest = root.join("steam/steamapps/appmanifest_322170.acf");
+        let updating = fs::read_to_string(&manifest).unwrap()
+            .replace("\"StateFlags\"\t\t\"4\"", "\"StateFlags\"\t\t\"1286\"\n\t\"buildid\"\t\t\"15423487\"\n\
+\t\"BytesToDownload\"\t\t\"1000\"\n\t\"BytesDownloaded\"\t\t\"250\"");
+        fs::write(&manifest, updating).unwrap();
+
+        let game = SteamGameFinder::at(root.join("steam")).get_game_info("322170").unwrap();
+        assert_eq!(game.build_id, Some(15423487));
+        assert_eq!((game.bytes_downloaded, game.bytes_to_download), (250, 1000));
+        assert!(!game.state_flags.is_fully_installed());
+        assert_eq!(game.state_flags.to_string(), "update required, fully installed, updating, update started");
+        assert!(StateFlags(StateFlags::FULLY_INSTALLED | StateFlags::APP_RUNNING).is_fully_installed());
+
+        let installer = GeodeInstaller::with_options(InstallOptions {
+            archive: Some(root.join("missing.zip")),
+            steam_root: Some(root.join("steam")),
+            ..Default::default()
+        }).unwrap();
+        match installer.install_to_steam() {
+            Err(InstallerError::GameNotReady(message)) => assert!(message.contains("250 of 1000 bytes"), "{}", message),
+            other => panic!("expected GameNotReady, got {:?}", other),
+        }
+
+        // Waiting gives up on an update that doesn't move, e.g. one queued behind other downloads
+        let installer = GeodeInstaller::with_options(InstallOptions {
+            archive: Some(root.join("missing.zip")),
+            steam_root: Some(root.join("steam")),
+            wait: true,
+            steam_stall_timeout: Some(Duration::ZERO),
+            ..Default::default()
+        }).unwrap();
+        match installer.ensure_game_ready() {
+            Err(InstallerError::GameNotReady(message)) => assert!(message.contains("no progress"), "{}", message),
+            other => panic!("expected GameNotReady, got {:?}", other),
+        }
+    }
+}
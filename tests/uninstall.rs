@@ -0,0 +1,56 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{self, USER_REG};
+    use geode_cli_installer::utils::geode_installer::GeodeInstaller;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn setup(name: &str) -> (PathBuf, PathBuf) {
+        let (_, prefix, game_dir) = common::setup(&format!("uninstall-{}", name));
+        fs::create_dir_all(game_dir.join("geode/mods")).unwrap();
+
+        for file in ["GeometryDash.exe", "Geode.dll", "GeodeUpdater.exe", "XInput1_4.dll"] {
+            fs::write(game_dir.join(file), b"").unwrap();
+        }
+        (prefix, game_dir)
+    }
+
+    #[test]
+    fn test_uninstall_removes_created_section() {
+        let (prefix, game_dir) = setup("section");
+        let patched = format!(
+            "{}\n\n[Software\\\\Wine\\\\DllOverrides] 1700000001\n#time=1da2\n\"xinput1_4\"=\"native,builtin\"\n",
+            USER_REG
+        );
+        fs::write(prefix.join("user.reg"), patched).unwrap();
+
+        let installer = GeodeInstaller::new().unwrap();
+        installer.uninstall_from_wine(&prefix, &game_dir, true).unwrap();
+
+        assert_eq!(fs::read_to_string(prefix.join("user.reg")).unwrap(), USER_REG);
+        assert!(game_dir.join("GeometryDash.exe").exists());
+        assert!(!game_dir.join("Geode.dll").exists());
+        assert!(!game_dir.join("XInput1_4.dll").exists());
+        assert!(game_dir.join("geode/mods").exists());
+    }
+
+    #[test]
+    fn test_uninstall_keeps_other_overrides() {
+        let (prefix, game_dir) = setup("entry");
+        let section = "[Software\\\\Wine\\\\DllOverrides] 1700000001\n#time=1da2\n\"d3d9\"=\"native\"\n";
+        let patched = format!(
+            "{}\n{}\"xinput1_4\"=\"native,builtin\"\n\n[Software\\\\Wine\\\\X11 Driver] 1700000002\n",
+            USER_REG, section
+        );
+        fs::write(prefix.join("user.reg"), patched).unwrap();
+
+        let installer = GeodeInstaller::new().unwrap();
+        installer.uninstall_from_wine(&prefix, &game_dir, false).unwrap();
+
+        let expected = format!("{}\n{}\n[Software\\\\Wine\\\\X11 Driver] 1700000002\n", USER_REG, section);
+        assert_eq!(fs::read_to_string(prefix.join("user.reg")).unwrap(), expected);
+        assert!(!game_dir.join("geode").exists());
+    }
+}
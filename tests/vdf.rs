@@ -0,0 +1,159 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use geode_cli_installer::utils::steam_game_finder::SteamLibrary;
+    use geode_cli_installer::utils::vdf::{Vdf, VdfValue};
+    use std::path::PathBuf;
+
+    const LIBRARY_FOLDERS: &str = "\"libraryfolders\"\n{\n\t// added by Steam\n\t\"0\"\n\t{\n\
+\t\t\"path\"\t\t\"/home/user/.local/share/Steam\"\n\t\t\"label\"\t\t\"\"\n\t\t\"apps\"\n\t\t{\n\
+\t\t\t\"322170\"\t\t\"216514290\"\n\t\t}\n\t}\n\t\"1\"   {  path \"/mnt/games/Steam\" }\n}\n";
+
+    #[test]
+    fn test_round_trip_keeps_formatting() {
+        let vdf = Vdf::parse(LIBRARY_FOLDERS).unwrap();
+        assert_eq!(vdf.to_string(), LIBRARY_FOLDERS);
+
+        let mut edited = vdf.clone();
+        edited.block_mut("libraryfolders").unwrap()
+            .block_mut("0").unwrap()
+            .set_string("label", "Games");
+        edited.block_mut("LIBRARYFOLDERS").unwrap()
+            .block_mut("1").unwrap()
+            .set_string("label", "External");
+
+        let expected = LIBRARY_FOLDERS
+            .replace("\"label\"\t\t\"\"", "\"label\"\t\t\"Games\"")
+            .replace("path \"/mnt/games/Steam\" }", "path \"/mnt/games/Steam\"\n\t\t\"label\"\t\t\"External\" }");
+        assert_eq!(edited.to_string(), expected);
+        assert_ne!(edited, vdf);
+        assert_eq!(Vdf::parse(&edited.to_string()).unwrap(), edited);
+    }
+
+    fn library_paths(vdf: &Vdf) -> Vec<PathBuf> {
+        SteamLibrary::from_vdf(vdf).into_iter().map(|library| library.path).collect()
+    }
+
+    #[test]
+    fn test_lookup_and_steam_helpers() {
+        let vdf = Vdf::parse(LIBRARY_FOLDERS).unwrap();
+        assert_eq!(library_paths(&vdf), vec![
+            PathBuf::from("/home/user/.local/share/Steam"),
+            PathBuf::from("/mnt/games/Steam"),
+        ]);
+        assert_eq!(vdf.path(&["libraryfolders", "0", "Apps"]).unwrap().string("322170"), Some("216514290"));
+
+        let legacy = Vdf::parse("\"LibraryFolders\"\n{\n\t\"TimeNextStatsReport\"\t\t\"1700000000\"\n\t\"1\"\t\t\"/mnt/games\"\n}\n").unwrap();
+        assert_eq!(library_paths(&legacy), vec![PathBuf::from("/mnt/games")]);
+
+        let duplicates = Vdf::parse("\"a\" \"1\" \"A\" \"2\" \"b\" { }").unwrap();
+        assert_eq!(duplicates.string("a"), Some("1"));
+        assert_eq!(duplicates.get_all("a").filter_map(VdfValue::as_str).collect::<Vec<_>>(), ["1", "2"]);
+        assert!(duplicates.block("b").unwrap().is_empty());
+
+        let mut config = Vdf::new();
+        config.local_config_apps_mut().block_or_insert("322170").set_string("LaunchOptions", "say \"hi\"");
+        assert_eq!(config.to_string(), "\"UserLocalConfigStore\"\n{\n\t\"Software\"\n\t{\n\t\t\"Valve\"\n\t\t{\n\
+\t\t\t\"Steam\"\n\t\t\t{\n\t\t\t\t\"apps\"\n\t\t\t\t{\n\t\t\t\t\t\"322170\"\n\t\t\t\t\t{\n\
+\t\t\t\t\t\t\"LaunchOptions\"\t\t\"say \\\"hi\\\"\"\n\t\t\t\t\t}\n\t\t\t\t}\n\t\t\t}\n\t\t}\n\t}\n}\n");
+        assert!(Vdf::parse("\"a\" { \"b\" \"c\"").is_err());
+    }
+
+    #[test]
+    fn test_full_keyvalues_syntax() {
+        let text = "// comment\n\"root\"\n{\n\tpath \"C:\\\\Games\\\\Steam\" // trailing\n\t\"quote\" \"say \\\"hi\\\"\"\n\
+\t\"raw\" \"C:\\Program Files\"\n\t\"launcher\" \"win.exe\" [$WIN32]\n\t\"launcher\" \"linux.sh\" [$LINUX]\n\
+\t\"ui\" [!$WINDOWS && $POSIX] { size 12 }\n\t\"ui\" [$X360] { size 40 }\n}\n";
+        let vdf = Vdf::parse(text).unwrap();
+        assert_eq!(vdf.to_string(), text);
+
+        let root = vdf.block("root").unwrap();
+        assert_eq!(root.string("path"), Some("C:\\Games\\Steam"));
+        assert_eq!(root.string("quote"), Some("say \"hi\""));
+        assert_eq!(root.string("raw"), Some("C:\\Program Files"));
+        assert_eq!(root.string("launcher"), Some("linux.sh"));
+        assert_eq!(root.get_all("launcher").count(), 1);
+        assert_eq!(root.entries().len(), 7);
+        assert_eq!(root.path(&["ui"]).unwrap().string("size"), Some("12"));
+
+        let errors = [
+            ("\"a\"\n{\n\t\"b\" \"c\n", "line 3, column 6: unterminated string"),
+            ("\"a\" {\n}\n}", "line 3, column 1: '}' without a matching '{'"),
+            ("\"a\" {\n\t\"b\"\n}", "line 3, column 1: missing value for 'b'"),
+            ("\"a\" \"b\" [$WIN32\n", "line 1, column 9: unterminated condition"),
+            ("\"a\" {", "line 1, column 6: missing '}' at end of file"),
+        ];
+        for (text, expected) in errors {
+            let error = Vdf::parse(text).unwrap_err().to_string();
+            assert!(error.ends_with(expected), "{:?}: {}", text, error);
+        }
+    }
+
+    #[test]
+    fn test_include_and_base() {
+        let dir = common::temp_dir("vdf-include");
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("main.vdf"), "#base \"sub/base.vdf\"\n#include \"sub/extra.vdf\"\n\"cfg\" { \"a\" \"1\" }\n").unwrap();
+        std::fs::write(dir.join("sub/base.vdf"), "\"cfg\" { \"a\" \"0\" \"b\" \"2\" }\n\"other\" \"x\"\n").unwrap();
+        std::fs::write(dir.join("sub/extra.vdf"), "\"extra\" \"y\"\n").unwrap();
+        std::fs::write(dir.join("loop.vdf"), "#include \"loop.vdf\"\n").unwrap();
+
+        let vdf = Vdf::load_resolved(&dir.join("main.vdf")).unwrap();
+        assert_eq!(vdf.path(&["cfg"]).unwrap().string("a"), Some("1"));
+        assert_eq!(vdf.path(&["cfg"]).unwrap().string("b"), Some("2"));
+        assert_eq!(vdf.string("other"), Some("x"));
+        assert_eq!(vdf.string("extra"), Some("y"));
+        assert!(vdf.get("#base").is_none());
+
+        assert!(Vdf::load_resolved(&dir.join("loop.vdf")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Mutate valid documents at random; parsing must never panic or hang, and anything
+    /// that parses must write back to something that parses the same
+    #[test]
+    fn test_fuzz_malformed_input() {
+        const ALPHABET: &[char] = &['"', '{', '}', '[', ']', '\\', '/', '\n', ' ', '\t', 'a', '$', '!', '|', '&', '#', '\u{feff}', 'é'];
+        let seeds = [LIBRARY_FOLDERS, "\"a\" \"b\" [$WIN32]\n\"c\" [!$X360] { d e }\n#include \"x\"\n"];
+
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut random = |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+
+        for i in 0..5000 {
+            let mut chars: Vec<char> = seeds[i % seeds.len()].chars().collect();
+            for _ in 0..1 + random(8) {
+                let pos = random(chars.len() + 1);
+                match random(4) {
+                    0 if pos < chars.len() => {
+                        chars.remove(pos);
+                    }
+                    1 => chars.insert(pos, ALPHABET[random(ALPHABET.len())]),
+                    2 => chars.truncate(pos),
+                    _ => {
+                        let end = (pos + random(16)).min(chars.len());
+                        let slice = chars[pos.min(end)..end].to_vec();
+                        chars.splice(pos.min(end)..pos.min(end), slice);
+                    }
+                }
+            }
+
+            let text: String = chars.into_iter().collect();
+            if let Ok(vdf) = Vdf::parse(&text) {
+                assert_eq!(vdf.to_string(), text);
+                let mut edited = vdf.clone();
+                edited.set_string("fuzz", "\"\\\n");
+                assert_eq!(Vdf::parse(&edited.to_string()).unwrap(), edited, "{:?}", text);
+            }
+        }
+
+        let deep = "\"a\" {".repeat(100_000);
+        assert!(Vdf::parse(&deep).unwrap_err().to_string().contains("nested too deeply"));
+    }
+}
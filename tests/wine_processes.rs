@@ -0,0 +1,48 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common;
+    use geode_cli_installer::utils::wine_processes::{PrefixProcess, WineProcesses};
+    use std::fs;
+    use std::os::unix::fs::{symlink, MetadataExt};
+    use std::path::Path;
+
+    fn fake_process(proc_root: &Path, pid: u32, comm: &str, cmdline: &str, environ: &str, cwd: &Path) {
+        let dir = proc_root.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("comm"), format!("{}\n", comm)).unwrap();
+        fs::write(dir.join("cmdline"), cmdline.replace(' ', "\0")).unwrap();
+        fs::write(dir.join("environ"), environ).unwrap();
+        symlink(cwd, dir.join("cwd")).unwrap();
+    }
+
+    #[test]
+    fn test_finds_wine_processes_of_prefix() {
+        let root = common::temp_dir("processes");
+        let proc_root = root.join("proc");
+        let prefix = root.join("pfx");
+        let other_prefix = root.join("other");
+        fs::create_dir_all(&proc_root).unwrap();
+        fs::create_dir_all(&prefix).unwrap();
+        fs::create_dir_all(&other_prefix).unwrap();
+
+        let meta = fs::metadata(&prefix).unwrap();
+        let server_dir = root.join(format!("server-{:x}-{:x}", meta.dev(), meta.ino()));
+        fs::create_dir_all(&server_dir).unwrap();
+
+        let env = format!("HOME=/home/user\0WINEPREFIX={}\0", prefix.display());
+        let other_env = format!("WINEPREFIX={}\0", other_prefix.display());
+        fake_process(&proc_root, 100, "wineserver", "wineserver", "", &server_dir);
+        fake_process(&proc_root, 200, "GeometryDash.e", "Z:\\games\\GeometryDash.exe", &env, &root);
+        fake_process(&proc_root, 300, "bash", "bash", &env, &root);
+        fake_process(&proc_root, 400, "wine64", "/usr/bin/wine64 notepad", &other_env, &root);
+
+        let processes = WineProcesses::at(proc_root).using_prefix(&prefix).unwrap();
+
+        assert_eq!(processes, vec![
+            PrefixProcess { pid: 100, name: "wineserver".into() },
+            PrefixProcess { pid: 200, name: "GeometryDash.exe".into() },
+        ]);
+    }
+}
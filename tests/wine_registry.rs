@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use geode_cli_installer::utils::wine_registry::{RegValue, RegistryFile};
+
+    const USER_REG: &str = "WINE REGISTRY Version 2\r\n;; All keys relative to \\\\User\r\n\r\n#arch=win64\r\n\r\n\
+[Software\\\\Wine\\\\DllOverrides\\\\Extra] 1700000000\r\n#time=1da1\r\n\"xinput1_4\"=\"builtin\"\r\n\r\n\
+[Software\\\\Wine\\\\DllOverrides] 1700000001\r\n#time=1da2\r\n\"d3d9\"=\"native\"\r\n\r\n\
+[Software\\\\Wine\\\\Fonts] 1700000002\r\n@=\"Tahoma\"\r\n\"Path\"=str(2):\"%WINDIR%\\\\fonts\\t\\\"x\\\"\"\r\n\
+\"LogPixels\"=dword:00000060\r\n\"Blob\"=hex:01,02,\\\r\n  ff\r\n\"Multi\"=hex(7):41,00,00,00\r\n";
+
+    #[test]
+    fn test_parses_values_and_roundtrips() {
+        let registry = RegistryFile::parse(USER_REG).unwrap();
+        assert_eq!(registry.to_string(), USER_REG);
+        assert_eq!(registry.arch(), Some("win64"));
+
+        let fonts = registry.section("software\\wine\\fonts").unwrap();
+        assert_eq!(fonts.timestamp(), Some(1700000002));
+        assert_eq!(fonts.get(""), Some(&RegValue::String("Tahoma".into())));
+        assert_eq!(fonts.get("path"), Some(&RegValue::TypedString {
+            kind: 2,
+            value: "%WINDIR%\\fonts\t\"x\"".into(),
+        }));
+        assert_eq!(fonts.get("LogPixels"), Some(&RegValue::Dword(0x60)));
+        assert_eq!(fonts.get("Blob"), Some(&RegValue::Hex { kind: None, bytes: vec![1, 2, 0xff] }));
+        assert_eq!(fonts.get("Multi"), Some(&RegValue::Hex { kind: Some(7), bytes: vec![0x41, 0, 0, 0] }));
+    }
+
+    #[test]
+    fn test_edits_only_touch_the_named_section() {
+        let mut registry = RegistryFile::parse(USER_REG).unwrap();
+
+        registry.section_mut("Software\\Wine\\DllOverrides").unwrap()
+            .set("xinput1_4", RegValue::String("native,builtin".into()));
+        let patched = registry.to_string();
+        assert_eq!(
+            patched,
+            USER_REG.replace("\"d3d9\"=\"native\"\r\n", "\"d3d9\"=\"native\"\r\n\"xinput1_4\"=\"native,builtin\"\r\n")
+        );
+
+        let mut registry = RegistryFile::parse(&patched).unwrap();
+        assert!(registry.section_mut("Software\\Wine\\DllOverrides").unwrap().remove("XINPUT1_4").is_some());
+        assert_eq!(registry.to_string(), USER_REG);
+    }
+
+    #[test]
+    fn test_new_section_is_removed_cleanly() {
+        let original = "WINE REGISTRY Version 2\n\n[Software\\\\Wine] 1700000000\n\"Version\"=\"win10\"\n";
+        let mut registry = RegistryFile::parse(original).unwrap();
+
+        registry.section_or_insert("Software\\Wine\\AppDefaults\\GeometryDash.exe")
+            .set("Version", RegValue::String("win7".into()));
+        let patched = RegistryFile::parse(&registry.to_string()).unwrap();
+        let section = patched.section("Software\\Wine\\AppDefaults\\GeometryDash.exe").unwrap();
+        assert_eq!(section.get("Version").and_then(RegValue::as_str), Some("win7"));
+        assert!(registry.to_string().contains("\n\n[Software\\\\Wine\\\\AppDefaults\\\\GeometryDash.exe] "));
+
+        assert!(registry.remove_section("Software\\Wine\\AppDefaults\\GeometryDash.exe"));
+        assert_eq!(registry.to_string(), original);
+    }
+
+    #[test]
+    fn test_reports_line_of_malformed_value() {
+        let error = RegistryFile::parse("WINE REGISTRY Version 2\n\n[Software] 1\n\"broken\"=dword:zz\n").unwrap_err();
+        assert!(error.to_string().contains("line 4"));
+    }
+}